@@ -4,14 +4,14 @@
 // file that was distributed with this source code.
 
 use chrono::{DateTime, FixedOffset};
-use clap::{crate_version, Arg, ArgAction, Command};
+use clap::{crate_version, Arg, ArgAction, ArgGroup, Command};
 use regex::Regex;
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     fs::File,
     fs::OpenOptions,
     hash::Hash,
-    io::{BufRead, BufReader, ErrorKind},
+    io::{BufRead, BufReader, Cursor, ErrorKind, Write},
     sync::OnceLock,
 };
 
@@ -27,6 +27,8 @@ use uucore::{
 use uucore::libc;
 
 mod json;
+mod pager;
+mod syslog;
 mod time_formatter;
 
 const ABOUT: &str = help_about!("dmesg.md");
@@ -36,9 +38,15 @@ const USAGE: &str = help_usage!("dmesg.md");
 pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     let mut dmesg = Dmesg::new();
     let matches: clap::ArgMatches = uu_app().try_get_matches_from(args)?;
+    if matches.get_flag(options::RAW) {
+        dmesg.output_format = OutputFormat::Raw;
+    }
     if matches.get_flag(options::JSON) {
         dmesg.output_format = OutputFormat::Json;
     }
+    if matches.get_flag(options::JSON_PRETTY) {
+        dmesg.output_format = OutputFormat::JsonPretty;
+    }
     if let Some(time_format) = matches.get_one::<String>(options::TIME_FORMAT) {
         dmesg.time_format = match &time_format[..] {
             "delta" => TimeFormat::Delta,
@@ -120,18 +128,130 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     if let Some(kmsg_file) = matches.get_one::<String>(options::KMSG_FILE) {
         dmesg.kmsg_file = kmsg_file;
         dmesg.kmsg_record_separator = 0;
+    } else if let Some(file) = matches.get_one::<String>(options::FILE) {
+        dmesg.text_file = Some(file);
     } else if cfg!(target_os = "windows") {
-        return Err(USimpleError::new(1, "Windows requires the use of '-K'"));
+        return Err(USimpleError::new(
+            1,
+            "Windows requires the use of '-K' or '-F'",
+        ));
+    }
+    if let Some(level) = matches.get_one::<u8>(options::CONSOLE_LEVEL) {
+        return syslog::set_console_level(*level);
+    }
+    if matches.get_flag(options::CONSOLE_OFF) {
+        return syslog::set_console(false);
     }
+    if matches.get_flag(options::CONSOLE_ON) {
+        return syslog::set_console(true);
+    }
+    if matches.get_flag(options::CLEAR) {
+        return syslog::clear();
+    }
+    dmesg.use_syslog = matches.get_flag(options::SYSLOG);
+    dmesg.read_clear = matches.get_flag(options::READ_CLEAR);
+    dmesg.use_pager = !matches.get_flag(options::NOPAGER);
+    dmesg.force_prefix = matches.get_flag(options::FORCE_PREFIX);
     dmesg.print()?;
+    // The syslog interface clears the buffer while reading it
+    if dmesg.read_clear && !dmesg.use_syslog {
+        syslog::clear()?;
+    }
     Ok(())
 }
 
+/// Parse the level for --console-level: either a level name, or its number
+/// counting from 1 (messages below that level are printed to the console).
+fn parse_console_level(value: &str) -> Result<u8, String> {
+    const LEVEL_NAMES: [&str; 8] = [
+        "emerg", "alert", "crit", "err", "warn", "notice", "info", "debug",
+    ];
+    let level = match LEVEL_NAMES.iter().position(|name| *name == value) {
+        Some(index) => index as u8 + 1,
+        None => value
+            .parse()
+            .map_err(|_| format!("unknown level '{value}'"))?,
+    };
+    if (1..=8).contains(&level) {
+        Ok(level)
+    } else {
+        Err(format!("level {level} is out of range 1..8"))
+    }
+}
+
 pub fn uu_app() -> Command {
     Command::new(uucore::util_name())
         .override_usage(format_usage(USAGE))
         .about(ABOUT)
         .version(crate_version!())
+        .arg(
+            Arg::new(options::CLEAR)
+                .short('C')
+                .long("clear")
+                .help("clear the kernel ring buffer")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all([options::READ_CLEAR, options::KMSG_FILE]),
+        )
+        .arg(
+            Arg::new(options::READ_CLEAR)
+                .short('c')
+                .long("read-clear")
+                .help("read and clear all messages")
+                .action(ArgAction::SetTrue)
+                .conflicts_with(options::KMSG_FILE),
+        )
+        .arg(
+            Arg::new(options::CONSOLE_LEVEL)
+                .short('n')
+                .long("console-level")
+                .value_name("level")
+                .help("set level of messages printed to console")
+                .value_parser(parse_console_level)
+                .action(ArgAction::Set)
+                .conflicts_with_all([
+                    options::CLEAR,
+                    options::READ_CLEAR,
+                    options::SYSLOG,
+                    options::KMSG_FILE,
+                ]),
+        )
+        .arg(
+            Arg::new(options::CONSOLE_OFF)
+                .short('D')
+                .long("console-off")
+                .help("disable printing messages to console (requires CAP_SYS_ADMIN)")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new(options::CONSOLE_ON)
+                .short('E')
+                .long("console-on")
+                .help("enable printing messages to console (requires CAP_SYS_ADMIN)")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new(options::SYSLOG)
+                .short('S')
+                .long("syslog")
+                .help("force to use syslog(2) rather than /dev/kmsg")
+                .action(ArgAction::SetTrue)
+                .conflicts_with(options::KMSG_FILE),
+        )
+        .arg(
+            Arg::new(options::FILE)
+                .short('F')
+                .long("file")
+                .value_name("file")
+                .help("use the file instead of the kernel log buffer")
+                .action(ArgAction::Set)
+                .conflicts_with_all([
+                    options::KMSG_FILE,
+                    options::SYSLOG,
+                    options::CLEAR,
+                    options::READ_CLEAR,
+                    options::CONSOLE_LEVEL,
+                ]),
+        )
         .arg(
             Arg::new(options::KMSG_FILE)
                 .short('K')
@@ -146,6 +266,13 @@ pub fn uu_app() -> Command {
                 .help("use JSON output format")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new(options::JSON_PRETTY)
+                .long("json-pretty")
+                .help("use indented JSON output with decoded facility and level")
+                .action(ArgAction::SetTrue)
+                .conflicts_with(options::JSON),
+        )
         .arg(
             Arg::new(options::TIME_FORMAT)
                 .long("time-format")
@@ -169,26 +296,83 @@ pub fn uu_app() -> Command {
                 .help("restrict output to defined levels")
                 .action(ArgAction::Append),
         )
+        .arg(
+            Arg::new(options::RAW)
+                .short('r')
+                .long("raw")
+                .help("print the records as they were read")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all([options::JSON, options::JSON_PRETTY]),
+        )
+        .arg(
+            Arg::new(options::FORCE_PREFIX)
+                .short('p')
+                .long("force-prefix")
+                .help("force timestamp output on each line of multi-line messages")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new(options::NOPAGER)
+                .short('P')
+                .long("nopager")
+                .help("do not pipe output into a pager")
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new(options::SINCE)
                 .long("since")
                 .help("display the lines since the specified time")
+                .allow_hyphen_values(true)
                 .action(ArgAction::Set),
         )
         .arg(
             Arg::new(options::UNTIL)
                 .long("until")
                 .help("display the lines until the specified time")
+                .allow_hyphen_values(true)
                 .action(ArgAction::Set),
         )
+        .group(
+            ArgGroup::new(options::CONSOLE)
+                .args([options::CONSOLE_OFF, options::CONSOLE_ON])
+                .conflicts_with_all([
+                    options::CONSOLE_LEVEL,
+                    options::CLEAR,
+                    options::READ_CLEAR,
+                    options::SYSLOG,
+                    options::KMSG_FILE,
+                    options::FILE,
+                    options::JSON,
+                    options::JSON_PRETTY,
+                    options::RAW,
+                    options::TIME_FORMAT,
+                    options::FACILITY,
+                    options::LEVEL,
+                    options::FORCE_PREFIX,
+                    options::SINCE,
+                    options::UNTIL,
+                ]),
+        )
 }
 
 mod options {
+    pub const CLEAR: &str = "clear";
+    pub const READ_CLEAR: &str = "read-clear";
+    pub const CONSOLE_LEVEL: &str = "console-level";
+    pub const CONSOLE_OFF: &str = "console-off";
+    pub const CONSOLE_ON: &str = "console-on";
+    pub const CONSOLE: &str = "console";
+    pub const SYSLOG: &str = "syslog";
+    pub const FILE: &str = "file";
     pub const KMSG_FILE: &str = "kmsg-file";
     pub const JSON: &str = "json";
+    pub const JSON_PRETTY: &str = "json-pretty";
     pub const TIME_FORMAT: &str = "time-format";
     pub const FACILITY: &str = "facility";
     pub const LEVEL: &str = "level";
+    pub const NOPAGER: &str = "nopager";
+    pub const FORCE_PREFIX: &str = "force-prefix";
+    pub const RAW: &str = "raw";
     pub const SINCE: &str = "since";
     pub const UNTIL: &str = "until";
 }
@@ -196,6 +380,11 @@ mod options {
 struct Dmesg<'a> {
     kmsg_file: &'a str,
     kmsg_record_separator: u8,
+    text_file: Option<&'a str>,
+    use_syslog: bool,
+    read_clear: bool,
+    use_pager: bool,
+    force_prefix: bool,
     output_format: OutputFormat,
     time_format: TimeFormat,
     facility_filters: Option<HashSet<Facility>>,
@@ -209,6 +398,11 @@ impl Dmesg<'_> {
         Dmesg {
             kmsg_file: "/dev/kmsg",
             kmsg_record_separator: b'\n',
+            text_file: None,
+            use_syslog: false,
+            read_clear: false,
+            use_pager: true,
+            force_prefix: false,
             output_format: OutputFormat::Normal,
             time_format: TimeFormat::Raw,
             facility_filters: None,
@@ -221,40 +415,72 @@ impl Dmesg<'_> {
     fn print(&self) -> UResult<()> {
         match self.output_format {
             OutputFormat::Json => self.print_json(),
+            OutputFormat::JsonPretty => self.print_json_pretty(),
             OutputFormat::Normal => self.print_normal(),
+            OutputFormat::Raw => self.print_raw(),
         }
     }
 
+    /// Prints the records as they were read, e.g. like `cat /dev/kmsg` for
+    /// the kmsg format, tags included.
+    fn print_raw(&self) -> UResult<()> {
+        let mut stdout = std::io::stdout().lock();
+        for record in self.try_filtered_iter()? {
+            writeln!(stdout, "{}", record?.raw)?;
+        }
+        Ok(())
+    }
+
+    fn print_json_pretty(&self) -> UResult<()> {
+        let records: UResult<Vec<Record>> = self.try_filtered_iter()?.collect();
+        println!("{}", json::serialize_records_pretty(&records?));
+        Ok(())
+    }
+
     fn print_json(&self) -> UResult<()> {
         let records: UResult<Vec<Record>> = self.try_filtered_iter()?.collect();
         println!("{}", json::serialize_records(&records?));
         Ok(())
     }
 
+    /// Prints the records as text, through a pager unless `--nopager` was
+    /// given. The JSON output is never paged.
     fn print_normal(&self) -> UResult<()> {
+        let mut output = pager::Output::start(self.use_pager);
+        let result = self.write_normal(&mut output);
+        output.finish()?;
+        result
+    }
+
+    fn write_normal(&self, out: &mut impl Write) -> UResult<()> {
         let mut reltime_formatter = time_formatter::ReltimeFormatter::new();
         let mut delta_formatter = time_formatter::DeltaFormatter::new();
         for record in self.try_filtered_iter()? {
             let record = record?;
-            match self.time_format {
+            let prefix = match self.time_format {
                 TimeFormat::Delta => {
-                    print!("[{}] ", delta_formatter.format(record.timestamp_us))
+                    format!("[{}] ", delta_formatter.format(record.timestamp_us))
                 }
                 TimeFormat::Reltime => {
-                    print!("[{}] ", reltime_formatter.format(record.timestamp_us))
-                }
-                TimeFormat::Ctime => {
-                    print!("[{}] ", time_formatter::ctime(record.timestamp_us))
-                }
-                TimeFormat::Iso => {
-                    print!("{} ", time_formatter::iso(record.timestamp_us))
+                    format!("[{}] ", reltime_formatter.format(record.timestamp_us))
                 }
-                TimeFormat::Raw => {
-                    print!("[{}] ", time_formatter::raw(record.timestamp_us))
-                }
-                TimeFormat::Notime => (),
+                TimeFormat::Ctime => format!("[{}] ", time_formatter::ctime(record.timestamp_us)),
+                TimeFormat::Iso => format!("{} ", time_formatter::iso(record.timestamp_us)),
+                TimeFormat::Raw => format!("[{}] ", time_formatter::raw(record.timestamp_us)),
+                TimeFormat::Notime => String::new(),
+            };
+            // The lines after the first one are lined up with it, unless the
+            // prefix is to be repeated on each of them
+            let indent = if self.force_prefix {
+                prefix.clone()
+            } else {
+                " ".repeat(prefix.chars().count())
+            };
+            let mut lines = record.message.split('\n');
+            writeln!(out, "{prefix}{}", lines.next().unwrap_or_default())?;
+            for line in lines {
+                writeln!(out, "{indent}{line}")?;
             }
-            println!("{}", record.message);
         }
         Ok(())
     }
@@ -268,7 +494,21 @@ impl Dmesg<'_> {
             .filter(Self::is_record_until(&self.until_filter)))
     }
 
-    fn try_iter(&self) -> UResult<RecordIterator> {
+    fn try_iter(&self) -> UResult<Box<dyn Iterator<Item = UResult<Record>>>> {
+        if let Some(text_file) = self.text_file {
+            let file =
+                File::open(text_file).map_err_context(|| format!("cannot open {text_file}"))?;
+            return Ok(Box::new(TextDumpIterator {
+                reader: BufReader::new(file),
+            }));
+        }
+        if self.use_syslog {
+            let buffer = syslog::read(self.read_clear)?;
+            return Ok(Box::new(TextDumpIterator {
+                reader: Cursor::new(buffer),
+            }));
+        }
+
         let mut open_option = OpenOptions::new();
         open_option.read(true);
 
@@ -286,10 +526,10 @@ impl Dmesg<'_> {
         }
 
         let file_reader = BufReader::new(file);
-        Ok(RecordIterator {
+        Ok(Box::new(RecordIterator {
             file_reader,
             kmsg_record_separator: self.kmsg_record_separator,
-        })
+        }))
     }
 
     fn is_record_in_set<T>(
@@ -334,6 +574,8 @@ impl Dmesg<'_> {
 enum OutputFormat {
     Normal,
     Json,
+    JsonPretty,
+    Raw,
 }
 
 enum TimeFormat {
@@ -396,7 +638,7 @@ impl Iterator for RecordIterator {
     type Item = UResult<Record>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.read_record_line() {
+        match self.read_record() {
             Err(e) => Some(Err(e)),
             Ok(None) => None,
             Ok(Some(line)) => match self.parse_record(&line) {
@@ -408,6 +650,26 @@ impl Iterator for RecordIterator {
 }
 
 impl RecordIterator {
+    /// Reads a record along with its continuation lines, which hold its tags.
+    fn read_record(&mut self) -> UResult<Option<String>> {
+        let Some(mut record) = self.read_record_line()? else {
+            return Ok(None);
+        };
+        // A read(2) from /dev/kmsg returns the continuation lines together
+        // with the record, but they are split up by the newline separator
+        while self.kmsg_record_separator == b'\n' && self.is_continuation_next() {
+            match self.read_record_line()? {
+                Some(line) => record.push_str(&line),
+                None => break,
+            }
+        }
+        Ok(Some(record))
+    }
+
+    fn is_continuation_next(&mut self) -> bool {
+        matches!(self.file_reader.fill_buf(), Ok(buf) if buf.first() == Some(&b' '))
+    }
+
     fn read_record_line(&mut self) -> UResult<Option<String>> {
         let mut buf = vec![];
         match self
@@ -428,14 +690,79 @@ impl RecordIterator {
     }
 
     fn parse_record(&self, record_line: &str) -> Option<Record> {
-        record_regex()
+        let mut record = record_regex()
             .captures_iter(record_line)
             .map(|c| c.extract())
             .filter_map(|(_, [pri_fac, seq, time, msg])| {
-                Record::from_str_fields(pri_fac, seq, time, msg.to_string()).ok()
+                Record::from_str_fields(pri_fac, seq, time, unescape_kmsg(msg)).ok()
             })
-            .next()
+            .next()?;
+        record.raw = record_line.trim_end_matches(['\n', '\0']).to_string();
+        // Continuation lines: " KEY=value", e.g. " SUBSYSTEM=pci"
+        record.tags = record
+            .raw
+            .lines()
+            .filter_map(|line| line.strip_prefix(' ')?.split_once('='))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        Some(record)
+    }
+}
+
+/// Records in the text format of syslog(2) and of saved `dmesg` output:
+/// `[<pri>][[seconds.micros] ]message`, one per line.
+struct TextDumpIterator<R: BufRead> {
+    reader: R,
+}
+
+impl<R: BufRead> Iterator for TextDumpIterator<R> {
+    type Item = UResult<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match self.reader.read_line(&mut line) {
+                Ok(0) => return None,
+                Ok(_) => {
+                    let line = line.trim_end_matches(['\n', '\r']);
+                    if line.is_empty() {
+                        continue;
+                    }
+                    if let Some(record) = Record::from_text_line(line) {
+                        return Some(Ok(record));
+                    }
+                }
+                Err(e) => return Some(Err(Box::new(UIoError::from(e)))),
+            }
+        }
+    }
+}
+
+/// Decodes the `\xNN` escapes used in kmsg messages for backslashes and
+/// non-printable characters, e.g. `\x0a` for the newlines of multi-line
+/// messages.
+fn unescape_kmsg(message: &str) -> String {
+    let mut bytes = Vec::with_capacity(message.len());
+    let mut rest = message.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let code = tail
+            .strip_prefix(b"x")
+            .and_then(|tail| tail.get(..2))
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u8::from_str_radix(digits, 16).ok());
+        match code {
+            Some(code) if byte == b'\\' => {
+                bytes.push(code);
+                rest = &tail[3..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
     }
+    String::from_utf8_lossy(&bytes).into_owned()
 }
 
 fn record_regex() -> &'static Regex {
@@ -452,14 +779,56 @@ fn record_regex() -> &'static Regex {
 
 static RECORD_REGEX: OnceLock<Regex> = OnceLock::new();
 
+fn text_record_regex() -> &'static Regex {
+    TEXT_RECORD_REGEX.get_or_init(|| {
+        Regex::new(r"^(?:<([0-9]+)>)?(?:\[ *([0-9]+)\.([0-9]+)\] ?)?(.*)$").expect("invalid regex.")
+    })
+}
+
+static TEXT_RECORD_REGEX: OnceLock<Regex> = OnceLock::new();
+
+/// Priority of text records without a `<pri>` prefix: kern.warn, the
+/// kernel's default message level.
+const DEFAULT_TEXT_PRIORITY: u32 = 4;
+
 struct Record {
     priority_facility: u32,
     _sequence: u64,
     timestamp_us: i64,
     message: String,
+    /// The record as it was read, continuation lines included
+    raw: String,
+    /// The key/value pairs of the continuation lines of kmsg records, such
+    /// as `SUBSYSTEM` and `DEVICE`
+    tags: HashMap<String, String>,
 }
 
 impl Record {
+    /// Parse a line of a [`TextDumpIterator`]. Lines without a timestamp
+    /// (e.g. continuation lines) are at time 0.
+    fn from_text_line(line: &str) -> Option<Record> {
+        let captures = text_record_regex().captures(line)?;
+        let priority_facility = match captures.get(1) {
+            Some(priority) => priority.as_str().parse().ok()?,
+            None => DEFAULT_TEXT_PRIORITY,
+        };
+        let timestamp_us = match (captures.get(2), captures.get(3)) {
+            (Some(seconds), Some(fraction)) => {
+                let micros = format!("{:0<6}", fraction.as_str());
+                seconds.as_str().parse::<i64>().ok()? * 1000000 + micros[..6].parse::<i64>().ok()?
+            }
+            _ => 0,
+        };
+        Some(Record {
+            priority_facility,
+            _sequence: 0,
+            timestamp_us,
+            message: captures[4].to_string(),
+            raw: line.to_string(),
+            tags: HashMap::new(),
+        })
+    }
+
     fn from_str_fields(pri_fac: &str, seq: &str, time: &str, msg: String) -> UResult<Record> {
         let pri_fac = str::parse(pri_fac);
         let seq = str::parse(seq);
@@ -470,6 +839,8 @@ impl Record {
                 _sequence: seq,
                 timestamp_us: time,
                 message: msg,
+                raw: String::new(),
+                tags: HashMap::new(),
             }),
             _ => Err(USimpleError::new(1, "Failed to parse record field(s)")),
         }
@@ -525,3 +896,51 @@ impl From<u32> for Facility {
         }
     }
 }
+
+impl Facility {
+    fn name(&self) -> &'static str {
+        match self {
+            Facility::Kern => "kern",
+            Facility::User => "user",
+            Facility::Mail => "mail",
+            Facility::Daemon => "daemon",
+            Facility::Auth => "auth",
+            Facility::Syslog => "syslog",
+            Facility::Lpr => "lpr",
+            Facility::News => "news",
+            Facility::Uucp => "uucp",
+            Facility::Cron => "cron",
+            Facility::Authpriv => "authpriv",
+            Facility::Ftp => "ftp",
+            Facility::Res0 => "res0",
+            Facility::Res1 => "res1",
+            Facility::Res2 => "res2",
+            Facility::Res3 => "res3",
+            Facility::Local0 => "local0",
+            Facility::Local1 => "local1",
+            Facility::Local2 => "local2",
+            Facility::Local3 => "local3",
+            Facility::Local4 => "local4",
+            Facility::Local5 => "local5",
+            Facility::Local6 => "local6",
+            Facility::Local7 => "local7",
+            Facility::Unknown => "unknown",
+        }
+    }
+}
+
+impl Level {
+    fn name(&self) -> &'static str {
+        match self {
+            Level::Emerg => "emerg",
+            Level::Alert => "alert",
+            Level::Crit => "crit",
+            Level::Err => "err",
+            Level::Warn => "warn",
+            Level::Notice => "notice",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Unknown => "unknown",
+        }
+    }
+}
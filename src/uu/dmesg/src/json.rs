@@ -4,7 +4,7 @@
 // file that was distributed with this source code.
 
 use serde::Serialize;
-use std::io;
+use std::{collections::BTreeMap, io};
 
 pub fn serialize_records(records: &Vec<crate::Record>) -> String {
     let json = Dmesg::from(records);
@@ -42,6 +42,49 @@ impl<'a> From<&'a Vec<crate::Record>> for Dmesg<'a> {
     }
 }
 
+/// Serialize records for --json-pretty, using the stable schema
+/// `{"timestamp_usec", "facility", "level", "message"}` for each record,
+/// plus `"tags"` for the records that have any.
+pub fn serialize_records_pretty(records: &[crate::Record]) -> String {
+    let json = PrettyDmesg {
+        dmesg: records
+            .iter()
+            .map(|record| PrettyRecord {
+                timestamp_usec: record.timestamp_us,
+                facility: crate::Facility::from(record.priority_facility).name(),
+                level: crate::Level::from(record.priority_facility).name(),
+                message: &record.message,
+                tags: sorted_tags(record),
+            })
+            .collect(),
+    };
+    serde_json::to_string_pretty(&json).unwrap()
+}
+
+#[derive(serde::Serialize)]
+struct PrettyDmesg<'a> {
+    dmesg: Vec<PrettyRecord<'a>>,
+}
+
+#[derive(serde::Serialize)]
+struct PrettyRecord<'a> {
+    timestamp_usec: i64,
+    facility: &'static str,
+    level: &'static str,
+    message: &'a str,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    tags: BTreeMap<&'a str, &'a str>,
+}
+
+/// The tags of a record, in a stable order
+fn sorted_tags(record: &crate::Record) -> BTreeMap<&str, &str> {
+    record
+        .tags
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.as_str()))
+        .collect()
+}
+
 struct DmesgFormatter {
     nesting_depth: i32,
 }
@@ -0,0 +1,139 @@
+// This file is part of the uutils util-linux package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Paging of the output through `$PAGER` (`less` by default) when it goes
+//! to a terminal, as util-linux does.
+
+use std::io::{self, IsTerminal, StdoutLock, Write};
+#[cfg(unix)]
+use std::{
+    env,
+    io::BufWriter,
+    process::{Child, ChildStdin, Command, Stdio},
+};
+use uucore::error::UResult;
+#[cfg(unix)]
+use uucore::error::{ExitCode, FromIo};
+
+/// Where the output goes: straight to stdout, or to the stdin of a pager.
+pub enum Output {
+    Stdout(StdoutLock<'static>),
+    #[cfg(unix)]
+    Pager {
+        child: Child,
+        stdin: BufWriter<ChildStdin>,
+        /// Set once the pager has quit, the rest of the output is discarded
+        closed: bool,
+    },
+}
+
+impl Output {
+    /// Starts the pager if `enabled` and stdout is a terminal, otherwise (or
+    /// if the pager can't be started) writes to stdout.
+    pub fn start(enabled: bool) -> Self {
+        let stdout = io::stdout();
+        if enabled && stdout.is_terminal() {
+            #[cfg(unix)]
+            if let Some(output) = Self::spawn_pager() {
+                return output;
+            }
+        }
+        Self::Stdout(stdout.lock())
+    }
+
+    #[cfg(unix)]
+    fn spawn_pager() -> Option<Self> {
+        let pager = env::var("PAGER").unwrap_or_else(|_| "less".to_string());
+        if pager.trim().is_empty() || pager == "cat" {
+            return None;
+        }
+        // Like util-linux: quit if the output fits on one screen, pass colors
+        // through, chop long lines and leave the screen alone on exit
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(&pager)
+            .env("LESS", env::var("LESS").unwrap_or_else(|_| "FRSX".into()))
+            .stdin(Stdio::piped())
+            .spawn()
+            .ok()?;
+        let stdin = BufWriter::new(child.stdin.take()?);
+        Some(Self::Pager {
+            child,
+            stdin,
+            closed: false,
+        })
+    }
+
+    /// Flushes the output and waits for the pager to exit, failing with its
+    /// exit code if it didn't succeed.
+    pub fn finish(self) -> UResult<()> {
+        match self {
+            Self::Stdout(mut stdout) => Ok(stdout.flush()?),
+            #[cfg(unix)]
+            Self::Pager {
+                mut child,
+                mut stdin,
+                closed,
+            } => {
+                if !closed {
+                    flush_pager(&mut stdin)?;
+                }
+                // Closing its stdin lets the pager see the end of the output
+                drop(stdin);
+                let status = child.wait().map_err_context(|| "waitpid failed".into())?;
+                match status.code() {
+                    Some(0) => Ok(()),
+                    Some(code) => Err(ExitCode::new(code)),
+                    // Killed by a signal
+                    None => Err(ExitCode::new(1)),
+                }
+            }
+        }
+    }
+}
+
+/// Flushes the pipe to the pager, which may have been quit before reading
+/// all of the output.
+#[cfg(unix)]
+fn flush_pager(stdin: &mut BufWriter<ChildStdin>) -> io::Result<()> {
+    match stdin.flush() {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Stdout(stdout) => stdout.write(buf),
+            #[cfg(unix)]
+            Self::Pager { stdin, closed, .. } => {
+                if *closed {
+                    return Ok(buf.len());
+                }
+                match stdin.write(buf) {
+                    Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {
+                        *closed = true;
+                        Ok(buf.len())
+                    }
+                    result => result,
+                }
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Stdout(stdout) => stdout.flush(),
+            #[cfg(unix)]
+            Self::Pager { stdin, closed, .. } => {
+                if !*closed {
+                    flush_pager(stdin)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
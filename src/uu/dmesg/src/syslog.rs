@@ -0,0 +1,106 @@
+// This file is part of the uutils util-linux package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Access to the kernel ring buffer through syslog(2), see klogctl(3).
+
+#[cfg(target_os = "linux")]
+use std::io;
+use uucore::error::UResult;
+#[cfg(not(target_os = "linux"))]
+use uucore::error::USimpleError;
+#[cfg(target_os = "linux")]
+use uucore::{error::FromIo, libc};
+
+#[cfg(target_os = "linux")]
+const SYSLOG_ACTION_READ_ALL: libc::c_int = 3;
+#[cfg(target_os = "linux")]
+const SYSLOG_ACTION_READ_CLEAR: libc::c_int = 4;
+#[cfg(target_os = "linux")]
+const SYSLOG_ACTION_CLEAR: libc::c_int = 5;
+#[cfg(target_os = "linux")]
+const SYSLOG_ACTION_CONSOLE_OFF: libc::c_int = 6;
+#[cfg(target_os = "linux")]
+const SYSLOG_ACTION_CONSOLE_ON: libc::c_int = 7;
+#[cfg(target_os = "linux")]
+const SYSLOG_ACTION_CONSOLE_LEVEL: libc::c_int = 8;
+#[cfg(target_os = "linux")]
+const SYSLOG_ACTION_SIZE_BUFFER: libc::c_int = 10;
+
+/// Read the whole kernel ring buffer, in the `<pri>[time] message` text
+/// format of syslog(2), clearing it afterwards if `clear` is set.
+#[cfg(target_os = "linux")]
+pub fn read(clear: bool) -> UResult<String> {
+    let size = klogctl(SYSLOG_ACTION_SIZE_BUFFER, &mut [], 0)?;
+    let mut buffer = vec![0; size];
+    let action = if clear {
+        SYSLOG_ACTION_READ_CLEAR
+    } else {
+        SYSLOG_ACTION_READ_ALL
+    };
+    let len = klogctl(
+        action,
+        &mut buffer,
+        size.try_into().unwrap_or(libc::c_int::MAX),
+    )?;
+    buffer.truncate(len);
+    Ok(String::from_utf8_lossy(&buffer).into_owned())
+}
+
+/// Clear the kernel ring buffer.
+#[cfg(target_os = "linux")]
+pub fn clear() -> UResult<()> {
+    klogctl(SYSLOG_ACTION_CLEAR, &mut [], 0).map(|_| ())
+}
+
+/// Set the level (1 to 8) below which messages are printed to the console.
+#[cfg(target_os = "linux")]
+pub fn set_console_level(level: u8) -> UResult<()> {
+    klogctl(SYSLOG_ACTION_CONSOLE_LEVEL, &mut [], level.into()).map(|_| ())
+}
+
+/// Stop or resume printing messages to the console.
+#[cfg(target_os = "linux")]
+pub fn set_console(enabled: bool) -> UResult<()> {
+    let action = if enabled {
+        SYSLOG_ACTION_CONSOLE_ON
+    } else {
+        SYSLOG_ACTION_CONSOLE_OFF
+    };
+    klogctl(action, &mut [], 0).map(|_| ())
+}
+
+#[cfg(target_os = "linux")]
+fn klogctl(action: libc::c_int, buffer: &mut [u8], len: libc::c_int) -> UResult<usize> {
+    let ret = unsafe { libc::klogctl(action, buffer.as_mut_ptr().cast(), len) };
+    if ret < 0 {
+        return Err(io::Error::last_os_error()).map_err_context(|| "klogctl failed".into());
+    }
+    Ok(ret as usize)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn read(_clear: bool) -> UResult<String> {
+    Err(unsupported())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn clear() -> UResult<()> {
+    Err(unsupported())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_console_level(_level: u8) -> UResult<()> {
+    Err(unsupported())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_console(_enabled: bool) -> UResult<()> {
+    Err(unsupported())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn unsupported() -> Box<dyn uucore::error::UError> {
+    USimpleError::new(1, "the syslog interface is only supported on Linux")
+}
@@ -3,7 +3,7 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
-use chrono::{DateTime, FixedOffset, TimeDelta};
+use chrono::{DateTime, FixedOffset, Local, TimeDelta};
 #[cfg(feature = "fixed-boot-time")]
 use chrono::{NaiveDate, NaiveTime};
 use std::sync::OnceLock;
@@ -117,11 +117,30 @@ impl DeltaFormatter {
     }
 }
 
+/// Parses a `--since`/`--until` value, either an absolute time or one
+/// relative to now such as `now`, `-10min` or `-1 hour`.
 pub fn parse_datetime(s: &str) -> UResult<DateTime<FixedOffset>> {
-    parse_datetime::parse_datetime(s)
+    parse_datetime::parse_datetime_at_date(now(), s)
         .map_err(|_| USimpleError::new(1, format!("invalid time value \"{s}\"")))
 }
 
+#[cfg(not(feature = "fixed-boot-time"))]
+fn now() -> DateTime<Local> {
+    Local::now()
+}
+
+#[cfg(feature = "fixed-boot-time")]
+fn now() -> DateTime<Local> {
+    // Shortly after the last record of the test fixtures
+    let date = NaiveDate::from_ymd_opt(2024, 11, 20).unwrap();
+    let time = NaiveTime::from_hms_opt(16, 0, 0).unwrap();
+    let tz = FixedOffset::east_opt(7 * 3600).unwrap();
+    chrono::NaiveDateTime::new(date, time)
+        .and_local_timezone(tz)
+        .unwrap()
+        .with_timezone(&Local)
+}
+
 pub fn datetime_from_microseconds_since_boot(microseconds: i64) -> DateTime<FixedOffset> {
     boot_time()
         .checked_add_signed(TimeDelta::microseconds(microseconds))
@@ -5,10 +5,23 @@
 
 use clap::{crate_version, Arg, ArgAction, ArgGroup, Command};
 #[cfg(target_os = "linux")]
-use std::{fs::File, io, os::fd::AsRawFd};
-#[cfg(target_os = "linux")]
-use uucore::{error::UIoError, libc};
+use std::{
+    fs::{self, File},
+    io, mem,
+    os::{
+        fd::AsRawFd,
+        unix::fs::{FileTypeExt, MetadataExt},
+    },
+    path::{Path, PathBuf},
+    ptr,
+    time::{Duration, Instant},
+};
 use uucore::{error::UResult, format_usage, help_about, help_usage};
+#[cfg(target_os = "linux")]
+use uucore::{
+    error::{UIoError, USimpleError},
+    libc,
+};
 
 const ABOUT: &str = help_about!("fsfreeze.md");
 const USAGE: &str = help_usage!("fsfreeze.md");
@@ -17,29 +30,182 @@ const USAGE: &str = help_usage!("fsfreeze.md");
 #[uucore::main]
 pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     let matches: clap::ArgMatches = uu_app().try_get_matches_from(args)?;
-    let mountpoint = matches.get_one::<String>("mountpoint").unwrap();
+    let path = Path::new(matches.get_one::<String>("mountpoint").unwrap());
+    let mountpoint = if path.metadata()?.file_type().is_block_device() {
+        resolve_mountpoint(path).ok_or_else(|| {
+            USimpleError::new(1, format!("{}: device is not mounted", path.display()))
+        })?
+    } else {
+        path.to_path_buf()
+    };
     let file = File::open(mountpoint)?;
     let metadata = file.metadata()?;
     if !metadata.is_dir() {
         return Err(UIoError::new(io::ErrorKind::InvalidData, "not a directory"));
     }
 
+    if let Some(seconds) = matches.get_one::<u64>("timeout") {
+        return freeze_with_timeout(&file, Duration::from_secs(*seconds));
+    }
+
     let (op_name, op_code) = if matches.get_flag("freeze") {
         ("freeze", linux_raw_sys::ioctl::FIFREEZE)
     } else {
         ("unfreeze", linux_raw_sys::ioctl::FITHAW)
     };
 
-    if unsafe { libc::ioctl(file.as_raw_fd(), op_code.into(), 0) } < 0 {
+    if let Err(e) = fs_ioctl(&file, op_code) {
         uucore::show_error!(
             "failed to {} the filesystem: {}",
             op_name,
-            UIoError::from(io::Error::last_os_error())
+            UIoError::from(e)
         );
     }
     Ok(())
 }
 
+/// Looks up where the block device `dev` is mounted, using
+/// `/proc/self/mountinfo`.
+///
+/// Mounts are matched on the device number, falling back to the mount source
+/// for filesystems that report an anonymous device number (e.g. btrfs). Bind
+/// mounts of a subdirectory are skipped in favour of the mount of the
+/// filesystem root.
+#[cfg(target_os = "linux")]
+fn resolve_mountpoint(dev: &Path) -> Option<PathBuf> {
+    let rdev = dev.metadata().ok()?.rdev();
+    let (major, minor) = unsafe { (libc::major(rdev), libc::minor(rdev)) };
+    let dev_number = format!("{major}:{minor}");
+    let dev_path = dev.canonicalize().ok()?;
+    let mountinfo = fs::read_to_string("/proc/self/mountinfo").ok()?;
+    find_mountpoint(&mountinfo, &dev_number, &dev_path)
+}
+
+/// Finds, in the contents of a mountinfo file, where the root of the
+/// filesystem on the device `dev_number` (`major:minor`) or `dev_path` is
+/// mounted.
+#[cfg(target_os = "linux")]
+fn find_mountpoint(mountinfo: &str, dev_number: &str, dev_path: &Path) -> Option<PathBuf> {
+    mountinfo.lines().find_map(|line| {
+        // 36 35 98:0 /mnt1 /mnt2 rw,noatime master:1 - ext3 /dev/root rw
+        let fields: Vec<&str> = line.split(' ').collect();
+        let separator = fields.iter().position(|field| *field == "-")?;
+        let (number, root, target) = (fields.get(2)?, fields.get(3)?, fields.get(4)?);
+        let source = fields.get(separator + 2)?;
+
+        let same_device = *number == dev_number
+            || Path::new(&unescape_mountinfo(source))
+                .canonicalize()
+                .is_ok_and(|source| source == dev_path);
+        (same_device && *root == "/").then(|| PathBuf::from(unescape_mountinfo(target)))
+    })
+}
+
+/// Decodes the octal escapes (`\040` for a space, etc.) used in mountinfo.
+#[cfg(target_os = "linux")]
+fn unescape_mountinfo(field: &str) -> String {
+    let mut bytes = Vec::with_capacity(field.len());
+    let mut rest = field.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let code = tail
+            .get(..3)
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u8::from_str_radix(digits, 8).ok());
+        match code {
+            Some(code) if byte == b'\\' => {
+                bytes.push(code);
+                rest = &tail[3..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(target_os = "linux")]
+fn fs_ioctl(file: &File, op_code: u32) -> io::Result<()> {
+    if unsafe { libc::ioctl(file.as_raw_fd(), op_code.into(), 0) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Freezes the filesystem, waits for `timeout` or until a termination signal
+/// arrives, and thaws it again.
+///
+/// The termination signals are blocked before freezing, so they can only end
+/// the wait early and never kill the process while the filesystem is frozen.
+#[cfg(target_os = "linux")]
+fn freeze_with_timeout(file: &File, timeout: Duration) -> UResult<()> {
+    let signals = termination_signals();
+    let mut old_mask: libc::sigset_t = unsafe { mem::zeroed() };
+    if unsafe { libc::pthread_sigmask(libc::SIG_BLOCK, &signals, &mut old_mask) } != 0 {
+        return Err(USimpleError::new(1, "failed to block signals"));
+    }
+
+    let result = fs_ioctl(file, linux_raw_sys::ioctl::FIFREEZE)
+        .map_err(|e| {
+            USimpleError::new(
+                1,
+                format!("failed to freeze the filesystem: {}", UIoError::from(e)),
+            )
+        })
+        .and_then(|()| {
+            wait_for_signal(&signals, timeout);
+            fs_ioctl(file, linux_raw_sys::ioctl::FITHAW).map_err(|e| {
+                USimpleError::new(
+                    1,
+                    format!("failed to unfreeze the filesystem: {}", UIoError::from(e)),
+                )
+            })
+        });
+
+    // A signal received while blocked stays pending and takes effect here,
+    // once the filesystem has been thawed.
+    unsafe { libc::pthread_sigmask(libc::SIG_SETMASK, &old_mask, ptr::null_mut()) };
+    result
+}
+
+#[cfg(target_os = "linux")]
+fn termination_signals() -> libc::sigset_t {
+    let mut set: libc::sigset_t = unsafe { mem::zeroed() };
+    unsafe {
+        libc::sigemptyset(&mut set);
+        for signal in [libc::SIGHUP, libc::SIGINT, libc::SIGQUIT, libc::SIGTERM] {
+            libc::sigaddset(&mut set, signal);
+        }
+    }
+    set
+}
+
+/// Waits until `timeout` has elapsed or one of the (blocked) `signals` is
+/// delivered, whichever comes first.
+#[cfg(target_os = "linux")]
+fn wait_for_signal(signals: &libc::sigset_t, timeout: Duration) {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return;
+        }
+        let ts = libc::timespec {
+            tv_sec: remaining.as_secs().try_into().unwrap_or(libc::time_t::MAX),
+            tv_nsec: remaining.subsec_nanos() as libc::c_long,
+        };
+        if unsafe { libc::sigtimedwait(signals, ptr::null_mut(), &ts) } >= 0 {
+            return;
+        }
+        // EAGAIN means the timeout expired, anything else (EINTR from an
+        // unrelated signal) just resumes waiting for the remaining time.
+        if io::Error::last_os_error().raw_os_error() == Some(libc::EAGAIN) {
+            return;
+        }
+    }
+}
+
 pub fn uu_app() -> Command {
     Command::new(uucore::util_name())
         .version(crate_version!())
@@ -60,9 +226,19 @@ pub fn uu_app() -> Command {
                 .help("unfreeze the filesystem")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("timeout")
+                .short('t')
+                .long("timeout")
+                .value_name("SECONDS")
+                .help("freeze the filesystem, then unfreeze it after SECONDS")
+                .value_parser(clap::value_parser!(u64))
+                .conflicts_with("unfreeze")
+                .action(ArgAction::Set),
+        )
         .arg(
             Arg::new("mountpoint")
-                .help("mountpoint of the filesystem")
+                .help("mountpoint of the filesystem, or a block device mounted on it")
                 .required(true)
                 .action(ArgAction::Set),
         )
@@ -83,3 +259,49 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
         "`fsfreeze` is available only on Linux.",
     ))
 }
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    const MOUNTINFO: &str = "\
+22 1 8:2 / / rw,relatime shared:1 - ext4 /dev/sda2 rw
+36 22 8:3 /data /srv/data rw,noatime shared:2 - ext4 /dev/sda3 rw
+37 22 8:3 / /mnt/my\\040disk rw,noatime shared:3 - ext4 /dev/sda3 rw
+38 22 0:45 / /home rw,relatime shared:4 - btrfs /dev/null rw
+";
+
+    #[test]
+    fn test_find_mountpoint_by_device_number() {
+        let dev = Path::new("/dev/sda2");
+        assert_eq!(
+            find_mountpoint(MOUNTINFO, "8:2", dev),
+            Some(PathBuf::from("/"))
+        );
+    }
+
+    #[test]
+    fn test_find_mountpoint_skips_bind_mounts_of_subdirectories() {
+        let dev = Path::new("/dev/sda3");
+        assert_eq!(
+            find_mountpoint(MOUNTINFO, "8:3", dev),
+            Some(PathBuf::from("/mnt/my disk"))
+        );
+    }
+
+    #[test]
+    fn test_find_mountpoint_by_source() {
+        // btrfs reports an anonymous device number, the source still matches
+        let dev = Path::new("/dev/null");
+        assert_eq!(
+            find_mountpoint(MOUNTINFO, "1:3", dev),
+            Some(PathBuf::from("/home"))
+        );
+    }
+
+    #[test]
+    fn test_find_mountpoint_not_mounted() {
+        let dev = Path::new("/dev/sda4");
+        assert_eq!(find_mountpoint(MOUNTINFO, "8:4", dev), None);
+    }
+}
@@ -10,11 +10,20 @@ mod platform;
 
 mod options {
     pub const SYSTEM: &str = "system";
+    pub const BAD_LOGINS: &str = "badlogins";
     pub const HOSTLAST: &str = "hostlast";
     pub const NO_HOST: &str = "nohostname";
+    pub const FULL_NAMES: &str = "fullnames";
+    pub const HOST_WIDTH: &str = "hostwidth";
     pub const LIMIT: &str = "limit";
     pub const DNS: &str = "dns";
+    pub const DNS_TIMEOUT: &str = "dns-timeout";
+    pub const IP: &str = "ip";
     pub const TIME_FORMAT: &str = "time-format";
+    pub const FULL_TIMES: &str = "fulltimes";
+    pub const PRESENT: &str = "present";
+    pub const SINCE: &str = "since";
+    pub const UNTIL: &str = "until";
     pub const USER_TTY: &str = "username";
     pub const FILE: &str = "file";
 }
@@ -35,11 +44,18 @@ pub fn uu_app() -> Command {
             Arg::new(options::FILE)
                 .short('f')
                 .long("file")
-                .action(ArgAction::Set)
-                .default_value("/var/log/wtmp")
-                .help("use a specific file instead of /var/log/wtmp")
+                .action(ArgAction::Append)
+                .help("use a specific file instead of /var/log/wtmp (may be repeated)")
                 .required(false),
         )
+        .arg(
+            Arg::new(options::BAD_LOGINS)
+                .short('b')
+                .long(options::BAD_LOGINS)
+                .action(ArgAction::SetTrue)
+                .required(false)
+                .help("show bad login attempts from /var/log/btmp (same as lastb)"),
+        )
         .arg(
             Arg::new(options::SYSTEM)
                 .short('x')
@@ -56,6 +72,25 @@ pub fn uu_app() -> Command {
                 .required(false)
                 .help("translate the IP number back into a hostname"),
         )
+        .arg(
+            Arg::new(options::DNS_TIMEOUT)
+                .long(options::DNS_TIMEOUT)
+                .value_name("seconds")
+                .action(ArgAction::Set)
+                .required(false)
+                .requires(options::DNS)
+                .value_parser(clap::value_parser!(u64))
+                .help("give up on a hostname lookup after <seconds> (default: 5)"),
+        )
+        .arg(
+            Arg::new(options::IP)
+                .short('i')
+                .long(options::IP)
+                .action(ArgAction::SetTrue)
+                .required(false)
+                .conflicts_with(options::DNS)
+                .help("display IP numbers in numbers-and-dots notation"),
+        )
         .arg(
             Arg::new(options::HOSTLAST)
                 .short('a')
@@ -72,16 +107,71 @@ pub fn uu_app() -> Command {
                 .required(false)
                 .help("don't display the hostname field"),
         )
+        .arg(
+            Arg::new(options::FULL_NAMES)
+                .short('w')
+                .long(options::FULL_NAMES)
+                .action(ArgAction::SetTrue)
+                .required(false)
+                .help("display full user and domain names"),
+        )
+        .arg(
+            Arg::new(options::HOST_WIDTH)
+                .long(options::HOST_WIDTH)
+                .value_name("N")
+                .action(ArgAction::Set)
+                .required(false)
+                .value_parser(clap::value_parser!(usize))
+                .help("width of the hostname field (default: 16, or 32 with --dns)"),
+        )
         .arg(
             Arg::new(options::LIMIT)
                 .short('n')
                 .long(options::LIMIT)
                 .action(ArgAction::Set)
                 .required(false)
-                .help("how many lines to show")
+                .help("how many lines to show (also -<number>)")
                 .value_parser(clap::value_parser!(i32))
                 .allow_negative_numbers(true),
         )
+        .arg(
+            Arg::new(options::PRESENT)
+                .short('p')
+                .long(options::PRESENT)
+                .value_name("time")
+                .action(ArgAction::Set)
+                .required(false)
+                .help("display who were present at the specified time"),
+        )
+        .arg(
+            Arg::new(options::SINCE)
+                .short('s')
+                .long(options::SINCE)
+                .value_name("time")
+                .action(ArgAction::Set)
+                .required(false)
+                .allow_hyphen_values(true)
+                .help("display the lines since the specified time"),
+        )
+        .arg(
+            Arg::new(options::UNTIL)
+                .short('t')
+                .long(options::UNTIL)
+                .value_name("time")
+                .action(ArgAction::Set)
+                .required(false)
+                .allow_hyphen_values(true)
+                .help("display the lines until the specified time"),
+        )
+        .arg(
+            Arg::new(options::FULL_TIMES)
+                .short('F')
+                .long(options::FULL_TIMES)
+                .action(ArgAction::SetTrue)
+                .required(false)
+                .conflicts_with(options::TIME_FORMAT)
+                .help("print full login and logout times and dates"),
+        )
         .arg(
             Arg::new(options::TIME_FORMAT)
                 .long(options::TIME_FORMAT)
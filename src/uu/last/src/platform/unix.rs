@@ -6,22 +6,30 @@
 use crate::options;
 use crate::uu_app;
 
+use uucore::error::FromIo;
 use uucore::error::UIoError;
 use uucore::error::UResult;
 
 use uucore::error::USimpleError;
 use uucore::utmpx::time::OffsetDateTime;
-use uucore::utmpx::{time, Utmpx};
+use uucore::utmpx::{time, Utmpx, LOGIN_PROCESS, USER_PROCESS};
 
+use std::collections::HashMap;
+use std::ffi::OsString;
 use std::fmt::Write;
 use std::fs;
 use std::io;
-use std::net::Ipv4Addr;
+use std::net::IpAddr;
 
+use std::ops::Range;
 use std::os::unix::fs::MetadataExt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::str::FromStr;
+use std::sync::mpsc;
+use std::thread;
 use std::time::Duration;
+use tempfile::NamedTempFile;
 
 fn get_long_usage() -> String {
     format!(
@@ -31,24 +39,71 @@ fn get_long_usage() -> String {
 }
 
 const WTMP_PATH: &str = "/var/log/wtmp";
+const BTMP_PATH: &str = "/var/log/btmp";
+const DNS_TIMEOUT_SECS: u64 = 5;
 static TIME_FORMAT_STR: [&str; 4] = ["notime", "short", "full", "iso"];
 
+/// util-linux accepts the number of lines to show as a bare `-<number>`
+/// option (e.g. `last -5`), which clap cannot express, so such arguments are
+/// rewritten into `-n<number>` before parsing.
+fn expand_number_shorthand(args: impl uucore::Args) -> Vec<OsString> {
+    let mut expanded: Vec<OsString> = vec![];
+    let mut options_ended = false;
+    for arg in args {
+        let previous = expanded.last().and_then(|prev| prev.to_str());
+        // `-n -5` and `--limit -5` pass a (negative) value, not the shorthand
+        let is_limit_value = previous
+            .is_some_and(|prev| prev == "-n" || (prev.len() > 2 && "--limit".starts_with(prev)));
+        let shorthand = arg
+            .to_str()
+            .and_then(|arg| arg.strip_prefix('-'))
+            .filter(|digits| !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()))
+            .map(|digits| OsString::from(format!("-n{digits}")));
+
+        if arg == "--" {
+            options_ended = true;
+        }
+        match shorthand {
+            Some(limit) if !options_ended && !is_limit_value => expanded.push(limit),
+            _ => expanded.push(arg),
+        }
+    }
+    expanded
+}
+
 pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     let matches = uu_app()
         .after_help(get_long_usage())
-        .try_get_matches_from(args)?;
+        .try_get_matches_from(expand_number_shorthand(args))?;
 
+    // util-linux installs lastb as a symlink to last
+    let bad_logins = matches.get_flag(options::BAD_LOGINS) || uucore::util_name() == "lastb";
     let system = matches.get_flag(options::SYSTEM);
-    let dns = matches.get_flag(options::DNS);
+    let host_mode = if matches.get_flag(options::DNS) {
+        HostMode::Dns
+    } else if matches.get_flag(options::IP) {
+        HostMode::Numeric
+    } else {
+        HostMode::Raw
+    };
     let hostlast = matches.get_flag(options::HOSTLAST);
     let nohost = matches.get_flag(options::NO_HOST);
+    let fullnames = matches.get_flag(options::FULL_NAMES);
+    let host_width = match matches.get_one::<usize>(options::HOST_WIDTH) {
+        Some(width) => *width,
+        None if host_mode == HostMode::Dns => DNS_HOST_WIDTH,
+        None => HOST_WIDTH,
+    };
     let limit: i32 = if let Some(num) = matches.get_one::<i32>(options::LIMIT) {
         *num
     } else {
         0 // Original implementation has 0 mean no limit (print all values)
     };
 
-    let time_format = if let Some(format) = matches.get_one::<String>(options::TIME_FORMAT) {
+    let full_times = matches.get_flag(options::FULL_TIMES);
+    let time_format = if full_times {
+        Ok("full".to_string())
+    } else if let Some(format) = matches.get_one::<String>(options::TIME_FORMAT) {
         let format_str = format.as_str().trim();
         if TIME_FORMAT_STR.contains(&format_str) {
             Ok(format.to_string())
@@ -62,10 +117,33 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
         Ok("short".to_string())
     }?;
 
-    let file: String = if let Some(files) = matches.get_one::<String>(options::FILE) {
-        files.to_string()
+    let dns_resolver = (host_mode == HostMode::Dns).then(|| {
+        let timeout = matches
+            .get_one::<u64>(options::DNS_TIMEOUT)
+            .copied()
+            .unwrap_or(DNS_TIMEOUT_SECS);
+        DnsResolver::new(Duration::from_secs(timeout))
+    });
+
+    let present = matches
+        .get_one::<String>(options::PRESENT)
+        .map(|value| parse_time_value(value))
+        .transpose()?;
+    let since = matches
+        .get_one::<String>(options::SINCE)
+        .map(|value| parse_time_value(value))
+        .transpose()?;
+    let until = matches
+        .get_one::<String>(options::UNTIL)
+        .map(|value| parse_time_value(value))
+        .transpose()?;
+
+    let files: Vec<String> = if let Some(files) = matches.get_many::<String>(options::FILE) {
+        files.cloned().collect()
+    } else if bad_logins {
+        vec![BTMP_PATH.to_string()]
     } else {
-        WTMP_PATH.to_string()
+        vec![WTMP_PATH.to_string()]
     };
 
     let user: Option<Vec<String>> =
@@ -87,19 +165,35 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
         last_reboot_ut: None,
         last_shutdown_ut: None,
         last_dead_ut: vec![],
+        earlier_boots: 0,
+        bad_logins,
         system,
-        dns,
+        host_mode,
         host_last: hostlast,
         no_host: nohost,
         limit,
-        file: file.to_string(),
+        files,
         users: user,
         time_format,
+        fullnames,
+        full_times,
+        host_width,
+        present,
+        since,
+        until,
+        dns_resolver,
+        rows: vec![],
+        sections: vec![],
     };
 
     last.exec()
 }
 
+const USER_WIDTH: usize = 8;
+const HOST_WIDTH: usize = 16;
+/// Resolved hostnames are usually much longer than IP addresses
+const DNS_HOST_WIDTH: usize = 32;
+
 const RUN_LEVEL_STR: &str = "runlevel";
 const REBOOT_STR: &str = "reboot";
 const SHUTDOWN_STR: &str = "shutdown";
@@ -108,14 +202,83 @@ struct Last {
     last_reboot_ut: Option<Utmpx>,
     last_shutdown_ut: Option<Utmpx>,
     last_dead_ut: Vec<Utmpx>,
+    /// Number of reboot records older than the record being processed
+    earlier_boots: usize,
+    bad_logins: bool,
     system: bool,
-    dns: bool,
+    host_mode: HostMode,
     host_last: bool,
     no_host: bool,
-    file: String,
+    files: Vec<String>,
     time_format: String,
     users: Option<Vec<String>>,
     limit: i32,
+    fullnames: bool,
+    /// Show the shutdown time instead of "down" for the sessions it ended
+    full_times: bool,
+    /// Width of the host field, a minimum with --fullnames
+    host_width: usize,
+    present: Option<OffsetDateTime>,
+    /// Only list the entries from --since to --until, both included
+    since: Option<OffsetDateTime>,
+    until: Option<OffsetDateTime>,
+    dns_resolver: Option<DnsResolver>,
+    rows: Vec<Row>,
+    /// The rows listed from each file, along with its "begins" footer
+    sections: Vec<(Range<usize>, String)>,
+}
+
+/// How a session listed by `last` came to an end
+enum SessionEnd {
+    /// Closed by a matching dead process record
+    Logout(OffsetDateTime),
+    /// Terminated by a system shutdown
+    Down(OffsetDateTime),
+    /// Cut short by a reboot without a recorded shutdown
+    Crash(OffsetDateTime),
+    /// Ended some time before a reboot, in a boot that is not part of the
+    /// (rotated) file, so whether it crashed is unknown
+    Gone(OffsetDateTime),
+    StillLoggedIn,
+    StillRunning,
+}
+
+impl SessionEnd {
+    /// The time the session ended, `None` if it is still ongoing
+    fn time(&self) -> Option<OffsetDateTime> {
+        match self {
+            Self::Logout(time) | Self::Down(time) | Self::Crash(time) | Self::Gone(time) => {
+                Some(*time)
+            }
+            Self::StillLoggedIn | Self::StillRunning => None,
+        }
+    }
+}
+
+/// How the host field of a record is displayed
+#[derive(Clone, Copy, PartialEq)]
+enum HostMode {
+    /// As stored in the utmp record
+    Raw,
+    /// Reverse-resolved into a hostname (--dns)
+    Dns,
+    /// As a numeric address, never resolved (--ip)
+    Numeric,
+}
+
+struct Row {
+    user: String,
+    line: String,
+    time: String,
+    host: String,
+    end_time: String,
+    delta: String,
+}
+
+fn parse_time_value(value: &str) -> UResult<OffsetDateTime> {
+    let invalid = || USimpleError::new(1, format!("invalid time value \"{value}\""));
+    let datetime = parse_datetime::parse_datetime(value).map_err(|_| invalid())?;
+    OffsetDateTime::from_unix_timestamp(datetime.timestamp()).map_err(|_| invalid())
 }
 
 fn is_numeric(s: &str) -> bool {
@@ -157,50 +320,209 @@ fn duration_string(duration: time::Duration) -> String {
     }
 }
 
-fn find_dns_name(ut: &Utmpx) -> String {
-    let default = Ipv4Addr::new(0, 0, 0, 0);
-    let ip = std::net::IpAddr::V4(Ipv4Addr::from_str(&ut.host()).unwrap_or(default));
+/// Convert a point in time to the local timezone, using the UTC offset that
+/// was in effect at that instant (so that DST changes are accounted for).
+fn local_time(time: OffsetDateTime) -> OffsetDateTime {
+    let offset = time::UtcOffset::local_offset_at(time).unwrap_or(time::UtcOffset::UTC);
+    time.to_offset(offset)
+}
 
-    if ip.to_string().trim() == "0.0.0.0" {
-        ip.to_string()
-    } else {
-        dns_lookup::lookup_addr(&ip).unwrap_or_default()
+#[inline]
+fn login_time(ut: &Utmpx) -> OffsetDateTime {
+    local_time(ut.login_time())
+}
+
+fn is_bad_login(ut: &Utmpx) -> bool {
+    !ut.user().is_empty() && (ut.record_type() == LOGIN_PROCESS || ut.record_type() == USER_PROCESS)
+}
+
+fn find_numeric_host(ut: &Utmpx) -> String {
+    let host = ut.host();
+    match IpAddr::from_str(host.trim()) {
+        Ok(ip) => ip.to_string(),
+        Err(_) => host,
     }
 }
 
+/// Reverse DNS resolution for --dns. Every address is looked up at most
+/// once, and a lookup that doesn't answer within `timeout` counts as failed.
+struct DnsResolver {
+    cache: HashMap<IpAddr, String>,
+    timeout: Duration,
+    lookup: fn(&IpAddr) -> io::Result<String>,
+}
+
+impl DnsResolver {
+    fn new(timeout: Duration) -> Self {
+        Self {
+            cache: HashMap::new(),
+            timeout,
+            lookup: dns_lookup::lookup_addr,
+        }
+    }
+
+    /// Resolve the IPv4 or IPv6 address in `host` into a hostname. As in
+    /// util-linux, the numeric address is kept if the lookup fails, and a
+    /// host that isn't an address is shown as it is.
+    fn resolve(&mut self, host: &str) -> String {
+        let ip = match IpAddr::from_str(host.trim()) {
+            Ok(ip) if !ip.is_unspecified() => ip,
+            _ => return host.to_string(),
+        };
+
+        let (lookup, timeout) = (self.lookup, self.timeout);
+        self.cache
+            .entry(ip)
+            .or_insert_with(|| {
+                lookup_with_timeout(lookup, ip, timeout).unwrap_or_else(|_| ip.to_string())
+            })
+            .clone()
+    }
+}
+
+fn lookup_with_timeout(
+    lookup: fn(&IpAddr) -> io::Result<String>,
+    ip: IpAddr,
+    timeout: Duration,
+) -> io::Result<String> {
+    let (sender, receiver) = mpsc::channel();
+    // The resolver can't be interrupted, so a lookup that times out is left
+    // to finish in the background
+    thread::spawn(move || sender.send(lookup(&ip)));
+    receiver
+        .recv_timeout(timeout)
+        .unwrap_or_else(|_| Err(io::Error::from(io::ErrorKind::TimedOut)))
+}
+
+/// The program decompressing `file`, going by its extension
+fn decompressor(file: &str) -> Option<&'static str> {
+    match Path::new(file).extension()?.to_str()? {
+        "gz" => Some("gzip"),
+        "xz" => Some("xz"),
+        "zst" => Some("zstd"),
+        _ => None,
+    }
+}
+
+/// Decompress `file` with `program` into a temporary file, which is removed
+/// when dropped, as the utmpx API can only read plain files given by their
+/// path.
+fn decompress(file: &str, program: &str) -> UResult<NamedTempFile> {
+    let (decompressed, output) = tempfile::Builder::new()
+        .prefix("last-")
+        .tempfile()
+        .and_then(|decompressed| {
+            let output = decompressed.reopen()?;
+            Ok((decompressed, output))
+        })
+        .map_err(|e| {
+            USimpleError::new(
+                1,
+                format!("cannot create a temporary file: {}", UIoError::from(e)),
+            )
+        })?;
+
+    let status = Command::new(program)
+        .args(["-dc", "--", file])
+        .stdout(output)
+        .status()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => USimpleError::new(
+                1,
+                format!("{file}: cannot decompress, {program} is not installed"),
+            ),
+            _ => USimpleError::new(
+                1,
+                format!("{file}: cannot run {program}: {}", UIoError::from(e)),
+            ),
+        })?;
+    if !status.success() {
+        return Err(USimpleError::new(
+            1,
+            format!("{file}: {program} failed to decompress the file"),
+        ));
+    }
+    Ok(decompressed)
+}
+
 impl Last {
     const TIME_FULL_FMT: &'static str = "[weekday repr:short] [month repr:short] [day padding:space] [hour]:[minute]:[second] [year]";
     const END_TIME_SHORT_FMT: &'static str = "[hour]:[minute]";
     const START_TIME_SHORT_FMT: &'static str =
         "[weekday repr:short] [month repr:short] [day padding:space] [hour]:[minute]";
     const TIME_ISO_FMT: &'static str =
-        "[year]-[month]-[day]T[hour]:[minute]:[second]+[offset_hour]:[offset_minute]";
+        "[year]-[month]-[day]T[hour]:[minute]:[second][offset_hour sign:mandatory]:[offset_minute]";
 
-    #[allow(clippy::cognitive_complexity)]
     fn exec(&mut self) -> UResult<()> {
+        // The limit applies to the combined listing of all files
+        let mut counter = 0;
+        for file in self.files.clone() {
+            let first_row = self.rows.len();
+            match self.read_file(&file, &mut counter) {
+                Ok(footer) => self.sections.push((first_row..self.rows.len(), footer)),
+                Err(e) => {
+                    // The files read so far are still listed
+                    self.rows.truncate(first_row);
+                    self.print_rows();
+                    return Err(e);
+                }
+            }
+        }
+
+        // Every timestamp has to be formatted before resolving hosts: a DNS
+        // lookup that timed out leaves a thread behind, and the local UTC
+        // offset cannot be determined soundly in a multi-threaded process.
+        self.print_rows();
+
+        Ok(())
+    }
+
+    /// Collect the rows listed from `file`, and return its "begins" footer.
+    #[allow(clippy::cognitive_complexity)]
+    fn read_file(&mut self, file: &str, counter: &mut i32) -> UResult<String> {
+        // Sessions never span several files
+        self.last_reboot_ut = None;
+        self.last_shutdown_ut = None;
+        self.last_dead_ut.clear();
+
+        // The utmpx API reads nothing from a file it can't open, without
+        // telling why
+        fs::metadata(file).map_err_context(|| format!("cannot open {file}"))?;
+
+        // Rotated files are often compressed (wtmp.1.gz)
+        let decompressed = decompressor(file)
+            .map(|program| decompress(file, program))
+            .transpose()?;
+        let records_path = decompressed
+            .as_ref()
+            .map_or(Path::new(file), |decompressed| decompressed.path());
+
         let mut ut_stack: Vec<Utmpx> = vec![];
         // For 'last' output, older output needs to be printed last (FILO), as
         // UtmpxIter does not implement Rev trait. A better implementation
         // might include implementing UtmpxIter as doubly linked
-        Utmpx::iter_all_records_from(&self.file).for_each(|ut| ut_stack.push(ut));
+        Utmpx::iter_all_records_from(records_path).for_each(|ut| ut_stack.push(ut));
+        self.earlier_boots = ut_stack.iter().filter(|ut| ut.user() == REBOOT_STR).count();
 
-        let mut counter = 0;
-        let mut first_ut_time = None;
+        // Taken before the loop, which stops early once the limit is reached
+        let first_ut_time = ut_stack.first().map(|ut| {
+            let first_login_time = ut.login_time();
+            self.utmp_file_time(
+                first_login_time.unix_timestamp(),
+                first_login_time.nanosecond().into(),
+            )
+        });
         while let Some(ut) = ut_stack.pop() {
-            if ut_stack.is_empty() {
-                // By the end of loop we will have the earliest time
-                // (This avoids getting into issues with the compiler)
-                let first_login_time = ut.login_time();
-                first_ut_time = Some(self.utmp_file_time(
-                    first_login_time.unix_timestamp(),
-                    first_login_time.nanosecond().into(),
-                ));
-            }
-
-            if counter >= self.limit && self.limit > 0 {
+            if *counter >= self.limit && self.limit > 0 {
                 break;
             }
-            if ut.is_user_process() {
+            if self.bad_logins {
+                // Every btmp entry with a user name is a failed attempt, login
+                // records the attempt as either a login or a user process
+                if is_bad_login(&ut) && self.print_bad_login(&ut) {
+                    *counter += 1;
+                }
+            } else if ut.is_user_process() {
                 let mut dead_proc: Option<Utmpx> = None;
                 if let Some(pos) = self
                     .last_dead_ut
@@ -210,20 +532,21 @@ impl Last {
                     dead_proc = Some(self.last_dead_ut.swap_remove(pos));
                 }
                 if self.print_user(&ut, dead_proc.as_ref()) {
-                    counter += 1;
+                    *counter += 1;
                 }
             } else if ut.user() == RUN_LEVEL_STR {
                 if self.print_runlevel(&ut) {
-                    counter += 1;
+                    *counter += 1;
                 }
             } else if ut.user() == SHUTDOWN_STR {
                 if self.print_shutdown(&ut) {
-                    counter += 1;
+                    *counter += 1;
                 }
                 self.last_shutdown_ut = Some(ut);
             } else if ut.user() == REBOOT_STR {
+                self.earlier_boots -= 1;
                 if self.print_reboot(&ut) {
-                    counter += 1;
+                    *counter += 1;
                 }
                 self.last_reboot_ut = Some(ut);
             } else if ut.user() == "" {
@@ -232,7 +555,7 @@ impl Last {
             }
         }
 
-        let path = std::path::absolute(&self.file)?;
+        let path = std::path::absolute(file)?;
         let path_str = path
             .file_name()
             .ok_or_else(|| {
@@ -248,17 +571,15 @@ impl Last {
                 "invalid character data (not UTF-8)",
             ))?;
 
-        if let Some(file_time) = first_ut_time {
-            println!("\n{} begins {}", path_str, file_time);
+        let file_time = if let Some(file_time) = first_ut_time {
+            file_time
         } else {
-            let secs = fs::metadata(&self.file)?.ctime();
-            let nsecs = fs::metadata(&self.file)?.ctime_nsec() as u64;
-            let file_time = self.utmp_file_time(secs, nsecs);
-
-            println!("\n{} begins {}", path_str, file_time);
-        }
+            let secs = fs::metadata(file)?.ctime();
+            let nsecs = fs::metadata(file)?.ctime_nsec() as u64;
+            self.utmp_file_time(secs, nsecs)
+        };
 
-        Ok(())
+        Ok(format!("{} begins {}", path_str, file_time))
     }
 
     #[inline]
@@ -276,13 +597,17 @@ impl Last {
             .unwrap_or(time::OffsetDateTime::UNIX_EPOCH)
             + Duration::from_nanos(nsecs);
 
-        let offset = time::UtcOffset::current_local_offset().unwrap_or(time::UtcOffset::UTC);
-        let offset_secs: u64 = offset.whole_seconds() as u64;
-
-        // Adding back the time to the offset so that offset_time is correct.
-        let offset_time = time.replace_offset(offset) + Duration::from_secs(offset_secs);
+        local_time(time).format(&time_format).unwrap_or_default()
+    }
 
-        offset_time.format(&time_format).unwrap_or_default()
+    #[inline]
+    fn host_string(&self, ut: &Utmpx) -> String {
+        match self.host_mode {
+            HostMode::Raw => ut.host(),
+            // Resolved once all rows are collected, see `print_rows`
+            HostMode::Dns => ut.host(),
+            HostMode::Numeric => find_numeric_host(ut),
+        }
     }
 
     #[inline]
@@ -297,7 +622,7 @@ impl Last {
         // "%b %e %H:%M"
         let time_format: Vec<time::format_description::FormatItem> =
             time::format_description::parse(description).unwrap_or_default();
-        ut.login_time().format(&time_format).unwrap_or_default()
+        login_time(ut).format(&time_format).unwrap_or_default()
     }
 
     #[inline]
@@ -320,66 +645,87 @@ impl Last {
         }
     }
 
+    /// Find out how the session started by `ut` ended, using the records
+    /// already seen during the (reverse) walk of the file.
     #[inline]
-    fn end_state_string(&self, ut: &Utmpx, dead_ut: Option<&Utmpx>) -> (String, String) {
+    fn session_end(&self, ut: &Utmpx, dead_ut: Option<&Utmpx>) -> SessionEnd {
         // This function takes a considerable amount of CPU cycles to complete;
         // root cause seems to be the ut.login_time function, which reads a
         // file to determine local offset for UTC. Perhaps this function
         // should be updated to save that UTC offset for subsequent calls
-        let mut proc_status: Option<&str> = None;
-        let curr_datetime = ut.login_time();
-
         if let Some(dead) = dead_ut {
-            let dead_datetime = dead.login_time();
-            let time_delta = duration_string(calculate_time_delta(&curr_datetime, &dead_datetime));
-            return (
-                self.end_time_string(proc_status, &dead_datetime),
-                time_delta.to_string(),
-            );
+            return SessionEnd::Logout(login_time(dead));
         }
 
-        let reboot_datetime: Option<OffsetDateTime>;
-        let shutdown_datetime: Option<OffsetDateTime>;
-        if let Some(reboot) = &self.last_reboot_ut {
-            reboot_datetime = Some(reboot.login_time());
-        } else {
-            reboot_datetime = None;
+        if let Some(shutdown) = &self.last_shutdown_ut {
+            return SessionEnd::Down(login_time(shutdown));
         }
 
-        if let Some(shutdown) = &self.last_shutdown_ut {
-            shutdown_datetime = Some(shutdown.login_time());
-        } else {
-            shutdown_datetime = None;
+        if !ut.is_user_process() {
+            return SessionEnd::StillRunning;
         }
 
-        if shutdown_datetime.is_none() {
-            if ut.is_user_process() {
-                // If a reboot has occurred since the user logged in, but not shutdown is recorded
-                // then a crash must have occurred.
-                if reboot_datetime.is_some() && reboot_datetime.unwrap() > ut.login_time() {
-                    ("- crash".to_string(), "".to_string())
+        // If a reboot has occurred since the user logged in, but not shutdown is recorded
+        // then a crash must have occurred. Unless the boot the user logged in
+        // to was rotated out of the file: then all that is known is that the
+        // session is gone.
+        match &self.last_reboot_ut {
+            Some(reboot) if login_time(reboot) > login_time(ut) => {
+                if self.earlier_boots == 0 {
+                    SessionEnd::Gone(login_time(reboot))
                 } else {
-                    ("  still logged in".to_string(), "".to_string())
+                    SessionEnd::Crash(login_time(reboot))
                 }
-            } else {
-                ("  still running".to_string(), "".to_string())
             }
-        } else {
-            let shutdown = shutdown_datetime
-                .unwrap_or_else(|| time::OffsetDateTime::from_unix_timestamp(0).unwrap());
-            let time_delta = duration_string(calculate_time_delta(&curr_datetime, &shutdown));
-            if ut.is_user_process() {
-                proc_status = Some("- down");
+            _ => SessionEnd::StillLoggedIn,
+        }
+    }
+
+    #[inline]
+    fn end_state_string(&self, ut: &Utmpx, end: &SessionEnd) -> (String, String) {
+        let curr_datetime = login_time(ut);
+        match end {
+            SessionEnd::Logout(logout) => (
+                self.end_time_string(None, logout),
+                duration_string(calculate_time_delta(&curr_datetime, logout)),
+            ),
+            SessionEnd::Down(shutdown) => {
+                let proc_status = if ut.is_user_process() && !self.full_times {
+                    Some("- down")
+                } else {
+                    None
+                };
+                (
+                    self.end_time_string(proc_status, shutdown),
+                    duration_string(calculate_time_delta(&curr_datetime, shutdown)),
+                )
             }
-            (
-                self.end_time_string(proc_status, &shutdown),
-                time_delta.to_string(),
-            )
+            SessionEnd::Crash(_) => ("- crash".to_string(), "".to_string()),
+            SessionEnd::Gone(_) => ("  gone - no logout".to_string(), "".to_string()),
+            SessionEnd::StillLoggedIn => ("  still logged in".to_string(), "".to_string()),
+            SessionEnd::StillRunning => ("  still running".to_string(), "".to_string()),
+        }
+    }
+
+    /// Check the session against --present: it must have started at or
+    /// before that time and not have ended before it. Also check that it
+    /// started within --since and --until.
+    #[inline]
+    fn is_selected(&self, ut: &Utmpx, end: &SessionEnd) -> bool {
+        let start = login_time(ut);
+        if self.since.is_some_and(|since| start < since)
+            || self.until.is_some_and(|until| start > until)
+        {
+            return false;
+        }
+        match self.present {
+            Some(present) => start <= present && end.time().map_or(true, |end| end >= present),
+            None => true,
         }
     }
 
     #[inline]
-    fn print_runlevel(&self, ut: &Utmpx) -> bool {
+    fn print_runlevel(&mut self, ut: &Utmpx) -> bool {
         if let Some(users) = &self.users {
             if !users
                 .iter()
@@ -391,13 +737,13 @@ impl Last {
         if self.system {
             let curr = (ut.pid() % 256) as u8 as char;
             let runlvline = format!("(to lvl {curr})");
-            let (end_date, delta) = self.end_state_string(ut, None);
-            let host = if self.dns {
-                find_dns_name(ut)
-            } else {
-                ut.host()
-            };
-            self.print_line(
+            let end = self.session_end(ut, None);
+            if !self.is_selected(ut, &end) {
+                return false;
+            }
+            let (end_date, delta) = self.end_state_string(ut, &end);
+            let host = self.host_string(ut);
+            self.push_row(
                 RUN_LEVEL_STR,
                 &runlvline,
                 &self.time_string(ut),
@@ -412,7 +758,7 @@ impl Last {
     }
 
     #[inline]
-    fn print_shutdown(&self, ut: &Utmpx) -> bool {
+    fn print_shutdown(&mut self, ut: &Utmpx) -> bool {
         if let Some(users) = &self.users {
             if !users.iter().any(|val| {
                 val.as_str().trim() == "system down" || val.as_str().trim() == ut.user().trim()
@@ -420,14 +766,14 @@ impl Last {
                 return false;
             }
         }
-        let host = if self.dns {
-            find_dns_name(ut)
-        } else {
-            ut.host()
-        };
+        let host = self.host_string(ut);
         if self.system {
-            let (end_date, delta) = self.end_state_string(ut, None);
-            self.print_line(
+            let end = self.session_end(ut, None);
+            if !self.is_selected(ut, &end) {
+                return false;
+            }
+            let (end_date, delta) = self.end_state_string(ut, &end);
+            self.push_row(
                 SHUTDOWN_STR,
                 "system down",
                 &self.time_string(ut),
@@ -441,8 +787,11 @@ impl Last {
         }
     }
 
+    /// Reboots are listed even without `--system`, as the pseudo-user
+    /// `reboot` logs in on every boot. Like the other entries, a reboot only
+    /// counts towards `--limit` when it is actually printed.
     #[inline]
-    fn print_reboot(&self, ut: &Utmpx) -> bool {
+    fn print_reboot(&mut self, ut: &Utmpx) -> bool {
         if let Some(users) = &self.users {
             if !users.iter().any(|val| {
                 val.as_str().trim() == ut.user().trim() || val.as_str().trim() == "system boot"
@@ -450,13 +799,13 @@ impl Last {
                 return false;
             }
         }
-        let (end_date, delta) = self.end_state_string(ut, None);
-        let host = if self.dns {
-            find_dns_name(ut)
-        } else {
-            ut.host()
-        };
-        self.print_line(
+        let end = self.session_end(ut, None);
+        if !self.is_selected(ut, &end) {
+            return false;
+        }
+        let (end_date, delta) = self.end_state_string(ut, &end);
+        let host = self.host_string(ut);
+        self.push_row(
             REBOOT_STR,
             "system boot",
             &self.time_string(ut),
@@ -469,7 +818,7 @@ impl Last {
     }
 
     #[inline]
-    fn print_user(&self, ut: &Utmpx, dead_ut: Option<&Utmpx>) -> bool {
+    fn print_user(&mut self, ut: &Utmpx, dead_ut: Option<&Utmpx>) -> bool {
         if let Some(users) = &self.users {
             if !users.iter().any(|val| {
                 val.as_str().trim() == ut.tty_device().as_str().trim()
@@ -480,15 +829,47 @@ impl Last {
         }
         let mut p = PathBuf::from("/dev");
         p.push(ut.tty_device().as_str());
-        let host = if self.dns {
-            find_dns_name(ut)
-        } else {
-            ut.host()
-        };
+        let host = self.host_string(ut);
+
+        let end = self.session_end(ut, dead_ut);
+        if !self.is_selected(ut, &end) {
+            return false;
+        }
+        let (end_date, delta) = self.end_state_string(ut, &end);
 
-        let (end_date, delta) = self.end_state_string(ut, dead_ut);
+        self.push_row(
+            ut.user().as_ref(),
+            ut.tty_device().as_ref(),
+            self.time_string(ut).as_str(),
+            &host,
+            &end_date,
+            &delta,
+        );
+
+        true
+    }
+
+    #[inline]
+    fn print_bad_login(&mut self, ut: &Utmpx) -> bool {
+        if let Some(users) = &self.users {
+            if !users.iter().any(|val| {
+                val.as_str().trim() == ut.tty_device().as_str().trim()
+                    || val.as_str().trim() == ut.user().trim()
+            }) {
+                return false;
+            }
+        }
+        let host = self.host_string(ut);
+
+        // A failed attempt has no session, so it "ends" when it started
+        let login_datetime = login_time(ut);
+        if !self.is_selected(ut, &SessionEnd::Logout(login_datetime)) {
+            return false;
+        }
+        let end_date = self.end_time_string(None, &login_datetime);
+        let delta = duration_string(time::Duration::ZERO);
 
-        self.print_line(
+        self.push_row(
             ut.user().as_ref(),
             ut.tty_device().as_ref(),
             self.time_string(ut).as_str(),
@@ -502,8 +883,8 @@ impl Last {
 
     #[inline]
     #[allow(clippy::too_many_arguments)]
-    fn print_line(
-        &self,
+    fn push_row(
+        &mut self,
         user: &str,
         line: &str,
         time: &str,
@@ -511,13 +892,73 @@ impl Last {
         end_time: &str,
         delta: &str,
     ) {
+        self.rows.push(Row {
+            user: user.to_string(),
+            line: line.to_string(),
+            time: time.to_string(),
+            host: host.to_string(),
+            end_time: end_time.to_string(),
+            delta: delta.to_string(),
+        });
+    }
+
+    fn print_rows(&mut self) {
+        if let Some(resolver) = &mut self.dns_resolver {
+            for row in &mut self.rows {
+                row.host = resolver.resolve(&row.host);
+            }
+        }
+
+        // Without --fullnames the fields are clamped to the util-linux widths
+        // (or --hostwidth), otherwise they are widened to fit the longest
+        // value of the listing
+        let (user_width, host_width) = if self.fullnames {
+            let max_width = |field: fn(&Row) -> &str, min: usize| {
+                self.rows
+                    .iter()
+                    .map(|row| field(row).chars().count())
+                    .fold(min, usize::max)
+            };
+            (
+                max_width(|row| &row.user, USER_WIDTH),
+                max_width(|row| &row.host, self.host_width),
+            )
+        } else {
+            (USER_WIDTH, self.host_width)
+        };
+
+        for (rows, footer) in &self.sections {
+            for row in &self.rows[rows.clone()] {
+                self.print_line(row, user_width, host_width);
+            }
+            println!("\n{footer}");
+        }
+    }
+
+    #[inline]
+    fn print_line(&self, row: &Row, user_width: usize, host_width: usize) {
         let mut buf = String::with_capacity(64);
-        let host_to_print = host.get(0..16).unwrap_or(host);
+        let Row {
+            user,
+            line,
+            time,
+            host,
+            end_time,
+            delta,
+        } = row;
+        let (user_to_print, host_to_print) = if self.fullnames {
+            (user.as_str(), host.as_str())
+        } else {
+            (
+                user.get(0..user_width).unwrap_or(user),
+                host.get(0..host_width).unwrap_or(host),
+            )
+        };
 
-        write!(buf, "{user:<8}").unwrap_or_default();
+        write!(buf, "{user_to_print:<user_width$}").unwrap_or_default();
         write!(buf, " {line:<12}").unwrap_or_default();
         if !self.host_last && !self.no_host {
-            write!(buf, " {host_to_print:<16}").unwrap_or_default();
+            write!(buf, " {host_to_print:<host_width$}").unwrap_or_default();
         }
 
         let time_size = 3 + 2 + 2 + 1 + 2;
@@ -533,3 +974,76 @@ impl Last {
         println!("{}", buf.trim_end());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_dns_resolver_caches_lookups() {
+        static LOOKUPS: AtomicUsize = AtomicUsize::new(0);
+        fn stub_lookup(ip: &IpAddr) -> io::Result<String> {
+            LOOKUPS.fetch_add(1, Ordering::SeqCst);
+            Ok(format!("host-{ip}.example"))
+        }
+
+        let mut resolver = DnsResolver {
+            lookup: stub_lookup,
+            ..DnsResolver::new(Duration::from_secs(1))
+        };
+        for _ in 0..3 {
+            assert_eq!(resolver.resolve("10.0.0.1"), "host-10.0.0.1.example");
+        }
+        assert_eq!(resolver.resolve("10.0.0.2"), "host-10.0.0.2.example");
+        assert_eq!(LOOKUPS.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_dns_resolver_timeout() {
+        fn slow_lookup(_: &IpAddr) -> io::Result<String> {
+            thread::sleep(Duration::from_secs(10));
+            Ok("too-late.example".to_string())
+        }
+
+        let mut resolver = DnsResolver {
+            lookup: slow_lookup,
+            ..DnsResolver::new(Duration::from_millis(50))
+        };
+        let start = std::time::Instant::now();
+        assert_eq!(resolver.resolve("10.0.0.1"), "10.0.0.1");
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_dns_resolver_falls_back_to_numeric() {
+        fn failing_lookup(_: &IpAddr) -> io::Result<String> {
+            Err(io::Error::new(io::ErrorKind::NotFound, "no PTR record"))
+        }
+
+        let mut resolver = DnsResolver {
+            lookup: failing_lookup,
+            ..DnsResolver::new(Duration::from_secs(1))
+        };
+        assert_eq!(resolver.resolve("192.0.2.7"), "192.0.2.7");
+        assert_eq!(resolver.resolve("2001:db8::1"), "2001:db8::1");
+        assert_eq!(resolver.resolve("workstation"), "workstation");
+        assert_eq!(resolver.resolve(""), "");
+    }
+
+    #[test]
+    fn test_dns_resolver_ipv6() {
+        fn stub_lookup(ip: &IpAddr) -> io::Result<String> {
+            match ip {
+                IpAddr::V6(_) => Ok("v6-host.example".to_string()),
+                IpAddr::V4(_) => Err(io::Error::from(io::ErrorKind::NotFound)),
+            }
+        }
+
+        let mut resolver = DnsResolver {
+            lookup: stub_lookup,
+            ..DnsResolver::new(Duration::from_secs(1))
+        };
+        assert_eq!(resolver.resolve("2001:db8::1"), "v6-host.example");
+    }
+}
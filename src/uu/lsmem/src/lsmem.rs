@@ -5,23 +5,27 @@
 
 mod utils;
 
-use clap::{crate_version, Command};
+use clap::builder::PossibleValue;
+use clap::{crate_version, Command, ValueEnum};
 use clap::{Arg, ArgAction};
-use serde::{Deserialize, Serialize};
+use serde::ser::{SerializeMap, Serializer};
+use serde::Serialize;
 use std::borrow::Borrow;
 use std::fs;
 use std::io::{self, BufRead, BufReader};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use uucore::{error::UResult, format_usage, help_about, help_usage};
+use uucore::{
+    error::{FromIo, UResult, USimpleError},
+    format_usage, help_about, help_usage,
+};
 
 use tabled::{
+    builder::Builder,
     settings::{
-        location::ByColumnName,
         object::{self, Rows},
         Alignment, Modify, Remove, Style,
     },
-    Table, Tabled,
 };
 
 const ABOUT: &str = help_about!("lsmem.md");
@@ -33,110 +37,150 @@ mod options {
     pub const JSON: &str = "json";
     pub const PAIRS: &str = "pairs";
     pub const RAW: &str = "raw";
+    pub const OUTPUT: &str = "output";
+    pub const OUTPUT_ALL: &str = "output-all";
+    pub const SPLIT: &str = "split";
+    pub const SYSROOT: &str = "sysroot";
+    pub const ALL: &str = "all";
 }
 
 // const BUFSIZ: usize = 1024;
 
-const PATH_SYS_MEMORY: &str = "/sys/devices/system/memory";
-const PATH_BLOCK_SIZE_BYTES: &str = "/sys/devices/system/memory/block_size_bytes";
-const PATH_VALID_ZONES: &str = "/sys/devices/system/memory/valid_zones";
+const PATH_SYS_MEMORY: &str = "sys/devices/system/memory";
+const PATH_SUB_BLOCK_SIZE_BYTES: &str = "block_size_bytes";
 const PATH_SUB_REMOVABLE: &str = "removable";
 const PATH_SUB_STATE: &str = "state";
+const PATH_SUB_VALID_ZONES: &str = "valid_zones";
 const NAME_MEMORY: &str = "memory";
 
-// struct ColDesc {
-//     name: &'static str, // Rust's equivalent to `const char *`
-//     whint: f64,         // Rust uses `f64` for double precision floating-point numbers
-//     flags: i32,         // Using `i32` for integers
-//     help: &'static str, // Rust's equivalent to `const char *`
-// }
-
-#[derive(Debug, Deserialize)]
-enum Columns {
-    #[serde(rename = "RANGE")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Column {
     Range,
-    #[serde(rename = "SIZE")]
     Size,
-    #[serde(rename = "STATE")]
     State,
-    #[serde(rename = "REMOVABLE")]
     Removable,
-    #[serde(rename = "BLOCK")]
     Block,
-    #[serde(rename = "NODE")]
     Node,
-    #[serde(rename = "ZONES")]
     Zones,
 }
-// const SCOLS_FL_RIGHT: i32 = 1;
-
-// static COLDESCS: [ColDesc; 7] = [
-//     ColDesc {
-//         name: "RANGE",
-//         whint: 0.0,
-//         flags: 0,
-//         help: "start and end address of the memory range",
-//     },
-//     ColDesc {
-//         name: "SIZE",
-//         whint: 5.0,
-//         flags: SCOLS_FL_RIGHT,
-//         help: "size of the memory range",
-//     },
-//     ColDesc {
-//         name: "STATE",
-//         whint: 0.0,
-//         flags: SCOLS_FL_RIGHT,
-//         help: "online status of the memory range",
-//     },
-//     ColDesc {
-//         name: "REMOVABLE",
-//         whint: 0.0,
-//         flags: SCOLS_FL_RIGHT,
-//         help: "memory is removable",
-//     },
-//     ColDesc {
-//         name: "BLOCK",
-//         whint: 0.0,
-//         flags: SCOLS_FL_RIGHT,
-//         help: "memory block number or blocks range",
-//     },
-//     ColDesc {
-//         name: "NODE",
-//         whint: 0.0,
-//         flags: SCOLS_FL_RIGHT,
-//         help: "numa node of memory",
-//     },
-//     ColDesc {
-//         name: "ZONES",
-//         whint: 0.0,
-//         flags: SCOLS_FL_RIGHT,
-//         help: "valid zones for the memory range",
-//     },
-// ];
-
-#[derive(Debug, Deserialize, PartialEq, Clone, Copy)]
+
+impl Column {
+    fn name(&self) -> &'static str {
+        match self {
+            Column::Range => "RANGE",
+            Column::Size => "SIZE",
+            Column::State => "STATE",
+            Column::Removable => "REMOVABLE",
+            Column::Block => "BLOCK",
+            Column::Node => "NODE",
+            Column::Zones => "ZONES",
+        }
+    }
+
+    fn help(&self) -> &'static str {
+        match self {
+            Column::Range => "start and end address of the memory range",
+            Column::Size => "size of the memory range",
+            Column::State => "online status of the memory range",
+            Column::Removable => "memory is removable",
+            Column::Block => "memory block number or blocks range",
+            Column::Node => "numa node of memory",
+            Column::Zones => "valid zones for the memory range",
+        }
+    }
+
+    fn is_right_aligned(&self) -> bool {
+        *self != Column::Range
+    }
+}
+
+impl ValueEnum for Column {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            Column::Range,
+            Column::Size,
+            Column::State,
+            Column::Removable,
+            Column::Block,
+            Column::Node,
+            Column::Zones,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.name()).help(self.help()))
+    }
+}
+
+const DEFAULT_COLUMNS: &[Column] = &[
+    Column::Range,
+    Column::Size,
+    Column::State,
+    Column::Removable,
+    Column::Block,
+];
+
+/// Columns that memory ranges are split by when `--split` is not given, as
+/// far as they are part of the output.
+///
+/// util-linux splits by every output column here, which means that ZONES
+/// only takes effect when the zones happen to be displayed, e.g. with
+/// `--output-all`. Splitting by zones has to be asked for explicitly with
+/// `--split=ZONES` instead, otherwise a range lists the zones of all of its
+/// blocks.
+const SPLIT_COLUMNS: &[Column] = &[Column::State, Column::Removable, Column::Node];
+
+fn parse_split_column(name: &str) -> Result<Column, String> {
+    match Column::from_str(name, true)? {
+        column @ (Column::State | Column::Removable | Column::Node | Column::Zones) => Ok(column),
+        column => Err(format!("cannot split by {}", column.name())),
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
 enum ZoneId {
-    #[serde(rename = "ZONE_DMA")]
     ZoneDma,
-    #[serde(rename = "ZONE_DMA32")]
     ZoneDma32,
-    #[serde(rename = "ZONE_NORMAL")]
     ZoneNormal,
-    #[serde(rename = "ZONE_HIGHMEM")]
     ZoneHighmem,
-    #[serde(rename = "ZONE_MOVABLE")]
     ZoneMovable,
-    #[serde(rename = "ZONE_DEVICE")]
     ZoneDevice,
-    #[serde(rename = "ZONE_NONE")]
     ZoneNone,
-    #[serde(rename = "ZONE_UNKNOWN")]
     ZoneUnknown,
-    #[serde(rename = "MAX_NR_ZONES")]
     MaxNrZones,
 }
 
+impl core::fmt::Display for ZoneId {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            ZoneId::ZoneDma => write!(f, "DMA"),
+            ZoneId::ZoneDma32 => write!(f, "DMA32"),
+            ZoneId::ZoneNormal => write!(f, "Normal"),
+            ZoneId::ZoneHighmem => write!(f, "Highmem"),
+            ZoneId::ZoneMovable => write!(f, "Movable"),
+            ZoneId::ZoneDevice => write!(f, "Device"),
+            ZoneId::ZoneNone => write!(f, "None"),
+            ZoneId::ZoneUnknown | ZoneId::MaxNrZones => write!(f, "Unknown"),
+        }
+    }
+}
+
+impl FromStr for ZoneId {
+    type Err = ();
+    fn from_str(input: &str) -> Result<ZoneId, Self::Err> {
+        match input.to_ascii_lowercase().as_str() {
+            "dma" => Ok(ZoneId::ZoneDma),
+            "dma32" => Ok(ZoneId::ZoneDma32),
+            "normal" => Ok(ZoneId::ZoneNormal),
+            "highmem" => Ok(ZoneId::ZoneHighmem),
+            "movable" => Ok(ZoneId::ZoneMovable),
+            "device" => Ok(ZoneId::ZoneDevice),
+            "none" => Ok(ZoneId::ZoneNone),
+            _ => Err(()),
+        }
+    }
+}
+
 #[derive(PartialEq, Clone)]
 enum MemoryState {
     Online,
@@ -192,46 +236,110 @@ impl MemoryBlock {
             removable: true,
         }
     }
+
+    /// Adds the zones of `other`, merged into this range, that aren't
+    /// listed yet.
+    fn merge_zones(&mut self, other: &MemoryBlock) {
+        for zone in &other.zones[..other.nr_zones] {
+            if !self.zones[..self.nr_zones].contains(zone) && self.nr_zones < self.zones.len() {
+                self.zones[self.nr_zones] = *zone;
+                self.nr_zones += 1;
+            }
+        }
+    }
 }
 
-#[derive(Tabled, Default, Serialize)]
+#[derive(Default)]
 struct TableRow {
-    #[tabled(rename = "RANGE")]
     range: String,
-    #[tabled(rename = "SIZE")]
     size: String,
-    #[tabled(rename = "STATE")]
     state: String,
-    #[tabled(rename = "REMOVABLE")]
     removable: String,
-    #[tabled(rename = "BLOCK")]
     block: String,
-    #[tabled(rename = "NODE")]
-    #[serde(skip_serializing)]
     node: String,
-    #[tabled(rename = "ZONES")]
-    #[serde(skip_serializing)]
     zones: String,
 }
 
 impl TableRow {
-    fn to_pairs_string(&self) -> String {
-        format!(
-            r#"RANGE="{}" SIZE="{}" STATE="{}" REMOVABLE="{}" BLOCK="{}""#,
-            self.range, self.size, self.state, self.removable, self.block
-        )
+    fn get(&self, column: Column) -> &str {
+        match column {
+            Column::Range => &self.range,
+            Column::Size => &self.size,
+            Column::State => &self.state,
+            Column::Removable => &self.removable,
+            Column::Block => &self.block,
+            Column::Node => &self.node,
+            Column::Zones => &self.zones,
+        }
     }
-    fn to_raw_string(&self) -> String {
-        format!(
-            r#"{} {} {} {} {}"#,
-            self.range, self.size, self.state, self.removable, self.block
-        )
+
+    fn to_pairs_string(&self, columns: &[Column]) -> String {
+        columns
+            .iter()
+            .map(|column| {
+                format!(
+                    r#"{}="{}""#,
+                    column.name(),
+                    escape_pairs_value(self.get(*column))
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Joins the values with single spaces, unescaped. None of the columns
+    /// can contain a space, so the output stays unambiguous.
+    fn to_raw_string(&self, columns: &[Column]) -> String {
+        columns
+            .iter()
+            .map(|column| self.get(*column))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Escapes a value for the `KEY="value"` output the way libsmartcols does,
+/// so that it can be safely evaluated by a shell.
+fn escape_pairs_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | '`' | '$') || c.is_ascii_control() {
+            escaped.push_str(&format!("\\x{:02x}", c as u32));
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+/// A [`TableRow`] restricted to the selected columns, serialized in their
+/// order, so that the JSON keys are exactly the requested columns.
+struct TableRowJson<'a> {
+    row: &'a TableRow,
+    columns: &'a [Column],
+}
+
+impl Serialize for TableRowJson<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.columns.len()))?;
+        for column in self.columns {
+            let key = column.name().to_lowercase();
+            let value = self.row.get(*column);
+            match column {
+                Column::Removable => map.serialize_entry(&key, &(value == "yes"))?,
+                // Every requested column is present, absent data (e.g. ZONES
+                // without valid_zones in sysfs) is null
+                _ if value.is_empty() => map.serialize_entry(&key, &None::<&str>)?,
+                _ => map.serialize_entry(&key, value)?,
+            }
+        }
+        map.end()
     }
 }
 
 #[derive(Serialize)]
-struct TableRowJson {
-    memory: Vec<TableRow>,
+struct TableJson<'a> {
+    memory: Vec<TableRowJson<'a>>,
 }
 
 struct Options {
@@ -249,6 +357,8 @@ struct Options {
     split_by_removable: bool,
     split_by_zones: bool,
     have_zones: bool,
+    columns: Vec<Column>,
+    sysroot: PathBuf,
 }
 
 struct Lsmem {
@@ -292,68 +402,75 @@ impl Options {
             split_by_removable: false,
             split_by_zones: false,
             have_zones: false,
+            columns: DEFAULT_COLUMNS.to_vec(),
+            sysroot: PathBuf::from("/"),
         }
     }
 }
 
-fn read_info(lsmem: &mut Lsmem, opts: &mut Options) {
-    lsmem.block_size = u64::from_str_radix(
-        &read_file_content::<String>(Path::new(PATH_BLOCK_SIZE_BYTES)).unwrap(),
-        16,
-    )
-    .unwrap();
-    lsmem.dirs = get_block_paths();
-    lsmem.dirs.sort_by(|a, b| {
-        let filename_a = a.to_str().unwrap().split('/').last().unwrap();
-        let filename_b = b.to_str().unwrap().split('/').last().unwrap();
-        let idx_a: u64 = filename_a[NAME_MEMORY.len()..].parse().unwrap();
-        let idx_b: u64 = filename_b[NAME_MEMORY.len()..].parse().unwrap();
-        idx_a.cmp(&idx_b)
-    });
+fn read_info(lsmem: &mut Lsmem, opts: &mut Options) -> UResult<()> {
+    let sys_memory = opts.sysroot.join(PATH_SYS_MEMORY);
+    let block_size = read_file_content::<String>(&sys_memory.join(PATH_SUB_BLOCK_SIZE_BYTES))
+        .map_err_context(|| "failed to read memory block size".into())?;
+    lsmem.block_size = u64::from_str_radix(&block_size, 16)
+        .map_err(|_| USimpleError::new(1, format!("invalid memory block size '{block_size}'")))?;
+    lsmem.dirs = get_block_paths(&sys_memory)?;
     lsmem.ndirs = lsmem.dirs.len();
-    for path in lsmem.dirs.iter() {
-        if memory_block_get_node(path).is_ok() {
-            opts.have_nodes = true;
-        }
-
-        let mut p = path.clone();
-        p.push("valid_zones");
-        if fs::read_dir(p).is_ok() {
-            opts.have_zones = true;
-        }
-
-        if opts.have_nodes && opts.have_zones {
-            break;
-        }
-    }
+    // Every block directory is scanned for its node once, here
+    let nodes = lsmem
+        .dirs
+        .iter()
+        .map(|path| memory_block_get_node(path))
+        .collect::<UResult<Vec<_>>>()?;
+    opts.have_nodes = nodes.iter().any(Option::is_some);
+    opts.have_zones = lsmem
+        .dirs
+        .iter()
+        .any(|path| path.join(PATH_SUB_VALID_ZONES).is_file());
 
     for i in 0..lsmem.ndirs {
-        let blk = memory_block_read_attrs(opts, &lsmem.dirs[i]);
+        let blk = memory_block_read_attrs(opts, &lsmem.dirs[i], nodes[i])?;
         if blk.state == MemoryState::Online {
             lsmem.mem_online += lsmem.block_size;
         } else {
             lsmem.mem_offline += lsmem.block_size;
         }
         if is_mergeable(lsmem, opts, &blk) {
-            lsmem.blocks[lsmem.nblocks - 1].count += 1;
+            let curr_block = &mut lsmem.blocks[lsmem.nblocks - 1];
+            curr_block.count += 1;
+            curr_block.merge_zones(&blk);
             continue;
         }
         lsmem.nblocks += 1;
         lsmem.blocks.push(blk.clone());
     }
+    Ok(())
 }
 
-fn get_block_paths() -> Vec<PathBuf> {
+/// Lists the `memory<N>` block directories, sorted by block index.
+fn get_block_paths(sys_memory: &Path) -> UResult<Vec<PathBuf>> {
     let mut paths = Vec::<PathBuf>::new();
-    for entry in fs::read_dir(PATH_SYS_MEMORY).unwrap() {
-        let entry = entry.unwrap();
-        let path = entry.path();
-        let filename = path.to_str().unwrap().split('/').last().unwrap();
-        if path.is_dir() && filename.starts_with(NAME_MEMORY) {
+    for entry in fs::read_dir(sys_memory)
+        .map_err_context(|| format!("cannot read {}", sys_memory.display()))?
+    {
+        let path = entry
+            .map_err_context(|| format!("cannot read {}", sys_memory.display()))?
+            .path();
+        if path.is_dir() && block_index(&path).is_some() {
             paths.push(path);
         }
     }
-    paths
+    paths.sort_by_key(|path| block_index(path));
+    Ok(paths)
+}
+
+/// The index of a memory block from its directory name, `memory<N>`.
+fn block_index(path: &Path) -> Option<u64> {
+    path.file_name()?
+        .to_str()?
+        .strip_prefix(NAME_MEMORY)?
+        .parse()
+        .ok()
 }
 
 fn is_mergeable(lsmem: &Lsmem, opts: &Options, blk: &MemoryBlock) -> bool {
@@ -391,54 +508,53 @@ fn is_mergeable(lsmem: &Lsmem, opts: &Options, blk: &MemoryBlock) -> bool {
     true
 }
 
-fn memory_block_get_node(path: &PathBuf) -> Result<i32, <i32 as FromStr>::Err> {
-    for entry in fs::read_dir(path).unwrap() {
-        let entry = entry.unwrap();
-        let path = entry.path();
-        let filename = path.to_str().unwrap().split('/').last().unwrap();
-        if path.is_dir() && filename.starts_with("node") {
-            return filename["node".len()..].parse();
+/// The NUMA node of a memory block, from its `node<N>` subdirectory, if any.
+fn memory_block_get_node(path: &Path) -> UResult<Option<i32>> {
+    for entry in fs::read_dir(path).map_err_context(|| format!("cannot read {}", path.display()))? {
+        let path = entry
+            .map_err_context(|| format!("cannot read {}", path.display()))?
+            .path();
+        let node = path
+            .file_name()
+            .and_then(|name| name.to_str()?.strip_prefix("node")?.parse().ok());
+        if node.is_some() && path.is_dir() {
+            return Ok(node);
         }
     }
-    Ok(-1)
+    Ok(None)
 }
 
-fn memory_block_read_attrs(opts: &Options, path: &PathBuf) -> MemoryBlock {
+fn memory_block_read_attrs(opts: &Options, path: &Path, node: Option<i32>) -> UResult<MemoryBlock> {
     let mut blk = MemoryBlock::new();
     blk.count = 1;
     blk.state = MemoryState::Unknown;
-    let filename = path.to_str().unwrap().split('/').last().unwrap();
-    blk.index = filename[NAME_MEMORY.len()..].parse().unwrap();
+    blk.index = block_index(path)
+        .ok_or_else(|| USimpleError::new(1, format!("{}: invalid memory block", path.display())))?;
 
-    let mut removable_path = path.clone();
-    removable_path.push(PATH_SUB_REMOVABLE);
-    blk.removable = read_file_content::<i32>(&removable_path).is_ok();
+    blk.removable = read_file_content::<i32>(&path.join(PATH_SUB_REMOVABLE)).is_ok();
 
-    let mut state_path = path.clone();
-    state_path.push(PATH_SUB_STATE);
-    if let Ok(state_raw) = read_file_content::<String>(&state_path) {
-        blk.state = MemoryState::from_str(&state_raw).unwrap();
+    if let Ok(state_raw) = read_file_content::<String>(&path.join(PATH_SUB_STATE)) {
+        blk.state = MemoryState::from_str(&state_raw).unwrap_or(MemoryState::Unknown);
     }
 
     if opts.have_nodes {
-        blk.node = memory_block_get_node(path).unwrap();
+        blk.node = node.unwrap_or(-1);
     }
 
     blk.nr_zones = 0;
     if opts.have_zones {
-        if let Ok(raw_content) = read_file_content::<String>(Path::new(PATH_VALID_ZONES)) {
-            let zone_toks = raw_content.split(' ').collect::<Vec<&str>>();
-            for (i, zone_tok) in zone_toks
-                .iter()
+        if let Ok(raw_content) = read_file_content::<String>(&path.join(PATH_SUB_VALID_ZONES)) {
+            for (i, zone_tok) in raw_content
+                .split_whitespace()
+                .take(ZoneId::MaxNrZones as usize)
                 .enumerate()
-                .take(std::cmp::min(zone_toks.len(), ZoneId::MaxNrZones as usize))
             {
-                blk.zones[i] = serde_json::from_str(zone_tok).unwrap();
+                blk.zones[i] = zone_tok.parse().unwrap_or(ZoneId::ZoneUnknown);
                 blk.nr_zones += 1;
             }
         }
     }
-    blk
+    Ok(blk)
 }
 
 fn create_table_rows(lsmem: &Lsmem, opts: &Options) -> Vec<TableRow> {
@@ -488,20 +604,34 @@ fn create_table_rows(lsmem: &Lsmem, opts: &Options) -> Vec<TableRow> {
             row.node = format!("{}", blk.node);
         }
 
+        // Zones
+        if opts.have_zones {
+            row.zones = blk.zones[..blk.nr_zones]
+                .iter()
+                .map(|zone| zone.to_string())
+                .collect::<Vec<_>>()
+                .join("/");
+        }
+
         table_rows.push(row);
     }
     table_rows
 }
 
 fn print_table(lsmem: &Lsmem, opts: &Options) {
-    let mut table = Table::new(create_table_rows(lsmem, opts));
-    table
-        .with(Style::blank())
-        .with(Modify::new(object::Columns::new(1..)).with(Alignment::right()));
+    let mut builder = Builder::default();
+    builder.push_record(opts.columns.iter().map(|column| column.name()));
+    for row in create_table_rows(lsmem, opts) {
+        builder.push_record(opts.columns.iter().map(|column| row.get(*column)));
+    }
 
-    // the default version
-    table.with(Remove::column(ByColumnName::new("NODE")));
-    table.with(Remove::column(ByColumnName::new("ZONES")));
+    let mut table = builder.build();
+    table.with(Style::blank());
+    for (i, column) in opts.columns.iter().enumerate() {
+        if column.is_right_aligned() {
+            table.with(Modify::new(object::Columns::single(i)).with(Alignment::right()));
+        }
+    }
 
     if opts.noheadings {
         table.with(Remove::row(Rows::first()));
@@ -511,13 +641,18 @@ fn print_table(lsmem: &Lsmem, opts: &Options) {
 }
 
 fn print_json(lsmem: &Lsmem, opts: &Options) {
-    let table_json = TableRowJson {
-        memory: create_table_rows(lsmem, opts),
+    let table_rows = create_table_rows(lsmem, opts);
+    let table_json = TableJson {
+        memory: table_rows
+            .iter()
+            .map(|row| TableRowJson {
+                row,
+                columns: &opts.columns,
+            })
+            .collect(),
     };
 
-    let mut table_json_string = serde_json::to_string_pretty(&table_json).unwrap();
-    table_json_string = table_json_string.replace("\"yes\"", "true");
-    table_json_string = table_json_string.replace("\"no\"", "false");
+    let table_json_string = serde_json::to_string_pretty(&table_json).unwrap();
     println!("{table_json_string}");
 }
 
@@ -525,7 +660,7 @@ fn print_pairs(lsmem: &Lsmem, opts: &Options) {
     let table_rows = create_table_rows(lsmem, opts);
     let table_pairs_string = table_rows
         .into_iter()
-        .map(|row| row.to_pairs_string())
+        .map(|row| row.to_pairs_string(&opts.columns))
         .collect::<Vec<_>>()
         .join("\n");
     println!("{table_pairs_string}");
@@ -535,12 +670,20 @@ fn print_raw(lsmem: &Lsmem, opts: &Options) {
     let table_rows = create_table_rows(lsmem, opts);
     let mut table_raw_string = String::new();
     for row in table_rows {
-        table_raw_string += &row.to_raw_string();
+        table_raw_string += &row.to_raw_string(&opts.columns);
         table_raw_string += "\n";
     }
     // remove the last newline
     table_raw_string.pop();
-    println!("RANGE SIZE STATE REMOVABLE BLOCK");
+    if !opts.noheadings {
+        let header = opts
+            .columns
+            .iter()
+            .map(|column| column.name())
+            .collect::<Vec<_>>()
+            .join(" ");
+        println!("{header}");
+    }
     println!("{table_raw_string}");
 }
 
@@ -568,15 +711,20 @@ fn print_summary(lsmem: &Lsmem, opts: &Options) {
     }
 }
 
-fn read_file_content<T: core::str::FromStr>(path: &Path) -> io::Result<T>
-where
-    T::Err: std::fmt::Debug, // Required to unwrap the result of T::from_str
-{
+/// Reads and parses the first line of a sysfs attribute, a value that fails
+/// to parse is reported as [`io::ErrorKind::InvalidData`].
+fn read_file_content<T: core::str::FromStr>(path: &Path) -> io::Result<T> {
     let file = fs::File::open(path)?;
     let mut reader = BufReader::new(file);
     let mut content = String::new();
     reader.read_line(&mut content)?;
-    Ok(content.trim().to_string().parse().unwrap())
+    let content = content.trim();
+    content.parse().map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid value '{content}'"),
+        )
+    })
 }
 
 #[uucore::main]
@@ -591,11 +739,50 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     opts.export = matches.get_flag(options::PAIRS);
     opts.raw = matches.get_flag(options::RAW);
 
+    if let Some(sysroot) = matches.get_one::<String>(options::SYSROOT) {
+        opts.sysroot = PathBuf::from(sysroot);
+    }
+
+    if matches.get_flag(options::OUTPUT_ALL) {
+        opts.columns = Column::value_variants().to_vec();
+    } else if let Some(columns) = matches.get_many::<Column>(options::OUTPUT) {
+        opts.columns = columns.copied().collect();
+    }
+
+    let split_columns: Vec<Column> = match matches.get_many::<Column>(options::SPLIT) {
+        Some(columns) => columns.copied().collect(),
+        None => SPLIT_COLUMNS
+            .iter()
+            .filter(|column| opts.columns.contains(column))
+            .copied()
+            .collect(),
+    };
+    opts.split_by_state = split_columns.contains(&Column::State);
+    opts.split_by_removable = split_columns.contains(&Column::Removable);
+    opts.split_by_node = split_columns.contains(&Column::Node);
+    opts.split_by_zones = split_columns.contains(&Column::Zones);
+
     if opts.json || opts.export || opts.raw {
         opts.want_summary = false;
     }
 
-    read_info(&mut lsmem, &mut opts);
+    opts.list_all = matches.get_flag(options::ALL);
+
+    read_info(&mut lsmem, &mut opts)?;
+
+    // Each block is listed on its own, so show its node and zones as well
+    // unless the columns were chosen explicitly
+    if opts.list_all
+        && !matches.get_flag(options::OUTPUT_ALL)
+        && !matches.contains_id(options::OUTPUT)
+    {
+        if opts.have_nodes {
+            opts.columns.push(Column::Node);
+        }
+        if opts.have_zones {
+            opts.columns.push(Column::Zones);
+        }
+    }
 
     if opts.want_table {
         if opts.json {
@@ -622,6 +809,14 @@ pub fn uu_app() -> Command {
         .about(ABOUT)
         .override_usage(format_usage(USAGE))
         .infer_long_args(true)
+        .arg(
+            Arg::new(options::ALL)
+                .short('a')
+                .long("all")
+                .help("list each individual memory block")
+                .action(ArgAction::SetTrue)
+                .conflicts_with(options::SPLIT),
+        )
         .arg(
             Arg::new(options::BYTES)
                 .short('b')
@@ -660,4 +855,67 @@ pub fn uu_app() -> Command {
                 .action(ArgAction::SetTrue)
                 .conflicts_with_all([options::JSON, options::PAIRS]),
         )
+        .arg(
+            Arg::new(options::OUTPUT)
+                .short('o')
+                .long("output")
+                .help("output columns")
+                .value_name("list")
+                .ignore_case(true)
+                .value_delimiter(',')
+                .value_parser(clap::value_parser!(Column))
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new(options::OUTPUT_ALL)
+                .long("output-all")
+                .help("output all columns")
+                .action(ArgAction::SetTrue)
+                .conflicts_with(options::OUTPUT),
+        )
+        .arg(
+            Arg::new(options::SPLIT)
+                .short('S')
+                .long("split")
+                .help(
+                    "split ranges by STATE, REMOVABLE, NODE and/or ZONES \
+                    (default: the STATE, REMOVABLE and NODE output columns)",
+                )
+                .value_name("list")
+                .value_delimiter(',')
+                .value_parser(parse_split_column)
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new(options::SYSROOT)
+                .short('s')
+                .long("sysroot")
+                .help("use the specified directory as system root")
+                .value_name("dir")
+                .action(ArgAction::Set),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pairs_escape_quotes() {
+        let row = TableRow {
+            range: "0x0-0x7ffffff".to_string(),
+            zones: r#"DMA "low""#.to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            row.to_pairs_string(&[Column::Range, Column::Zones]),
+            r#"RANGE="0x0-0x7ffffff" ZONES="DMA \x22low\x22""#
+        );
+    }
+
+    #[test]
+    fn test_pairs_escape_shell_characters() {
+        assert_eq!(escape_pairs_value(r"a\b`c$d"), r"a\x5cb\x60c\x24d");
+        assert_eq!(escape_pairs_value("a\tb"), r"a\x09b");
+    }
 }
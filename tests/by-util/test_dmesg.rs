@@ -165,6 +165,20 @@ fn test_filter_multiple() {
     });
 }
 
+#[test]
+fn test_no_pager_when_not_a_terminal() {
+    for args in [&[][..], &["--nopager"], &["-P"]] {
+        new_ucmd!()
+            .env("PAGER", "false")
+            .args(args)
+            .arg("--kmsg-file")
+            .arg("kmsg.input.1")
+            .arg("--time-format=raw")
+            .succeeds()
+            .stdout_is_templated_fixture("test_kmsg_time_format_raw.expected", &[("\r\n", "\n")]);
+    }
+}
+
 #[test]
 fn test_since_until() {
     new_ucmd!()
@@ -177,6 +191,48 @@ fn test_since_until() {
         .stdout_is_templated_fixture("test_since_until.expected", &[("\r\n", "\n")]);
 }
 
+#[test]
+fn test_since_until_relative() {
+    // With the fixed-boot-time feature, "now" is 2024-11-20 16:00:00 +0700
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input")
+        .arg("--time-format=iso")
+        .arg("--since")
+        .arg("-1 hour")
+        .succeeds()
+        .stdout_only(
+            "2024-11-20T15:10:52,866807+07:00 LOG_DEBUG LOG_SYSLOG\n\
+             2024-11-20T15:27:32,866807+07:00 LOG_DEBUG LOG_USER\n\
+             2024-11-20T15:44:12,866807+07:00 LOG_DEBUG LOG_UUCP\n",
+        );
+
+    // The last record is at 15:44:12
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input")
+        .arg("--time-format=iso")
+        .arg("--since=-16min")
+        .succeeds()
+        .stdout_only("2024-11-20T15:44:12,866807+07:00 LOG_DEBUG LOG_UUCP\n");
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input")
+        .arg("--since=-15min")
+        .succeeds()
+        .no_output();
+
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input")
+        .arg("--time-format=iso")
+        .arg("--since=-20min")
+        .arg("--until")
+        .arg("now")
+        .succeeds()
+        .stdout_only("2024-11-20T15:44:12,866807+07:00 LOG_DEBUG LOG_UUCP\n");
+}
+
 #[test]
 fn test_since_until_invalid_time() {
     let options = ["--since", "--until"];
@@ -187,3 +243,303 @@ fn test_since_until_invalid_time() {
             .stderr_only("dmesg: invalid time value \"definitely-invalid\"\n");
     }
 }
+
+#[test]
+fn test_clear_conflicts_with_kmsg_file() {
+    for clear in ["--clear", "--read-clear"] {
+        new_ucmd!()
+            .arg(clear)
+            .arg("--kmsg-file")
+            .arg("kmsg.input")
+            .fails()
+            .code_is(1)
+            .stderr_contains(format!(
+                "the argument '{clear}' cannot be used with '--kmsg-file <kmsg-file>'"
+            ));
+    }
+}
+
+#[test]
+fn test_clear_conflicts_with_read_clear() {
+    new_ucmd!()
+        .arg("-C")
+        .arg("-c")
+        .fails()
+        .code_is(1)
+        .stderr_contains("the argument '--clear' cannot be used with '--read-clear'");
+}
+
+#[test]
+fn test_syslog_conflicts_with_kmsg_file() {
+    new_ucmd!()
+        .arg("--syslog")
+        .arg("--kmsg-file")
+        .arg("kmsg.input")
+        .fails()
+        .code_is(1)
+        .stderr_contains("the argument '--syslog' cannot be used with '--kmsg-file <kmsg-file>'");
+}
+
+#[test]
+fn test_console_level_invalid() {
+    for (level, error) in [
+        ("0", "level 0 is out of range 1..8"),
+        ("9", "level 9 is out of range 1..8"),
+        ("verbose", "unknown level 'verbose'"),
+    ] {
+        new_ucmd!()
+            .arg("--console-level")
+            .arg(level)
+            .fails()
+            .code_is(1)
+            .stderr_contains(error);
+    }
+}
+
+#[test]
+fn test_console_level_conflicts_with_reading() {
+    for action in ["--clear", "--read-clear", "--syslog"] {
+        new_ucmd!()
+            .arg("-n")
+            .arg("debug")
+            .arg(action)
+            .fails()
+            .code_is(1)
+            .stderr_contains("the argument '--console-level <level>' cannot be used with");
+    }
+}
+
+#[test]
+fn test_console_off_conflicts_with_console_on() {
+    new_ucmd!()
+        .arg("--console-off")
+        .arg("--console-on")
+        .fails()
+        .code_is(1)
+        .stderr_contains("the argument '--console-off' cannot be used with '--console-on'");
+}
+
+#[test]
+fn test_console_on_off_conflict_with_reading() {
+    for toggle in ["--console-off", "--console-on"] {
+        for action in [
+            &["--clear"][..],
+            &["--read-clear"],
+            &["--syslog"],
+            &["--console-level", "debug"],
+            &["--kmsg-file", "kmsg.input"],
+            &["--json"],
+            &["--raw"],
+            &["--level", "err"],
+        ] {
+            new_ucmd!()
+                .arg(toggle)
+                .args(action)
+                .fails()
+                .code_is(1)
+                .stderr_contains(format!("the argument '{toggle}' cannot be used with"));
+        }
+    }
+}
+
+#[test]
+fn test_console_on_off_help() {
+    new_ucmd!()
+        .arg("--help")
+        .succeeds()
+        .stdout_contains("-D, --console-off")
+        .stdout_contains("-E, --console-on")
+        .stdout_contains("(requires CAP_SYS_ADMIN)");
+}
+
+#[test]
+fn test_text_file() {
+    new_ucmd!()
+        .arg("--file")
+        .arg("text.input")
+        .succeeds()
+        .no_stderr()
+        .stdout_is(
+            "[    0.000000] Linux version 6.1.0\n\
+            [    1.500000] usb 1-1: device descriptor read error\n\
+            [   12.345678] EXT4-fs (sda1): mounted filesystem\n\
+            [    0.000000] continuation without timestamp\n",
+        );
+}
+
+#[test]
+fn test_text_file_filters() {
+    // Lines without a <pri> prefix are kern.warn
+    new_ucmd!()
+        .arg("-F")
+        .arg("text.input")
+        .arg("--level=err,warn")
+        .arg("--time-format=iso")
+        .succeeds()
+        .stdout_is(
+            "2024-11-18T19:34:14,366807+07:00 usb 1-1: device descriptor read error\n\
+            2024-11-18T19:34:25,212485+07:00 EXT4-fs (sda1): mounted filesystem\n\
+            2024-11-18T19:34:12,866807+07:00 continuation without timestamp\n",
+        );
+
+    new_ucmd!()
+        .arg("-F")
+        .arg("text.input")
+        .arg("--since=2024-11-18T19:34:14+07:00")
+        .arg("--time-format=notime")
+        .succeeds()
+        .stdout_is(
+            "usb 1-1: device descriptor read error\n\
+            EXT4-fs (sda1): mounted filesystem\n",
+        );
+}
+
+#[test]
+fn test_text_file_conflicts_with_kmsg_file() {
+    new_ucmd!()
+        .arg("--file")
+        .arg("text.input")
+        .arg("--kmsg-file")
+        .arg("kmsg.input")
+        .fails()
+        .code_is(1)
+        .stderr_contains(
+            "the argument '--file <file>' cannot be used with '--kmsg-file <kmsg-file>'",
+        );
+}
+
+#[test]
+fn test_json_is_valid() {
+    let result = new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input")
+        .arg("--json")
+        .succeeds();
+    let json: serde_json::Value = serde_json::from_str(result.stdout_str()).unwrap();
+    assert!(json["dmesg"].is_array());
+}
+
+#[test]
+fn test_json_pretty() {
+    let result = new_ucmd!()
+        .arg("--file")
+        .arg("text.input")
+        .arg("--json-pretty")
+        .succeeds();
+    let json: serde_json::Value = serde_json::from_str(result.stdout_str()).unwrap();
+    let records = json["dmesg"].as_array().unwrap();
+    assert_eq!(records.len(), 4);
+    for record in records {
+        let mut keys: Vec<&str> = record
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(|key| key.as_str())
+            .collect();
+        keys.sort_unstable();
+        assert_eq!(keys, ["facility", "level", "message", "timestamp_usec"]);
+    }
+    assert_eq!(
+        records[1],
+        serde_json::json!({
+            "timestamp_usec": 1500000,
+            "facility": "kern",
+            "level": "err",
+            "message": "usb 1-1: device descriptor read error",
+        })
+    );
+}
+
+#[test]
+fn test_json_pretty_conflicts_with_json() {
+    new_ucmd!()
+        .arg("--json")
+        .arg("--json-pretty")
+        .fails()
+        .code_is(1)
+        .stderr_contains("the argument '--json' cannot be used with '--json-pretty'");
+}
+
+#[test]
+fn test_raw() {
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.tags.input")
+        .arg("--raw")
+        .succeeds()
+        .stdout_only(
+            "6,1,1500000,-;pci 0000:00:01.0: enabling device\n \
+             SUBSYSTEM=pci\n \
+             DEVICE=+pci:0000:00:01.0\n\
+             4,2,2500000,-;plain message\n",
+        );
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.tags.input")
+        .arg("-r")
+        .arg("--level=warn")
+        .succeeds()
+        .stdout_only("4,2,2500000,-;plain message\n");
+}
+
+#[test]
+fn test_raw_conflicts_with_json() {
+    new_ucmd!()
+        .arg("--raw")
+        .arg("--json")
+        .fails()
+        .code_is(1)
+        .stderr_contains("the argument '--raw' cannot be used with '--json'");
+}
+
+#[test]
+fn test_json_tags() {
+    let result = new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.tags.input")
+        .arg("--json-pretty")
+        .succeeds();
+    let json: serde_json::Value = serde_json::from_str(result.stdout_str()).unwrap();
+    assert_eq!(
+        json["dmesg"][0]["tags"],
+        serde_json::json!({"SUBSYSTEM": "pci", "DEVICE": "+pci:0000:00:01.0"})
+    );
+    assert!(json["dmesg"][1].get("tags").is_none());
+
+    // --json sticks to the util-linux fields
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.tags.input")
+        .arg("--json")
+        .succeeds()
+        .stdout_does_not_contain("tags");
+}
+
+#[test]
+fn test_multi_line_message() {
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.multiline.input")
+        .succeeds()
+        .stdout_only(
+            "[    1.000000] first line\n\
+             \x20              second line\n\
+             \x20              third\n\
+             [    2.000000] back\\slash \\xzz\n",
+        );
+}
+
+#[test]
+fn test_force_prefix() {
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.multiline.input")
+        .arg("--force-prefix")
+        .succeeds()
+        .stdout_only(
+            "[    1.000000] first line\n\
+             [    1.000000] second line\n\
+             [    1.000000] third\n\
+             [    2.000000] back\\slash \\xzz\n",
+        );
+}
@@ -21,9 +21,21 @@ fn test_operations_mutually_exclusive() {
         .stderr_contains("the argument '--freeze' cannot be used with '--unfreeze'");
 }
 
+#[test]
+fn test_timeout_conflicts_with_unfreeze() {
+    new_ucmd!()
+        .arg("--unfreeze")
+        .arg("--timeout=1")
+        .arg("/foo")
+        .fails()
+        .code_is(1)
+        .stderr_contains("the argument '--unfreeze' cannot be used with '--timeout <SECONDS>'");
+}
+
 #[cfg(target_os = "linux")]
 mod linux {
     use crate::common::util::TestScenario;
+    use std::path::Path;
 
     #[test]
     fn test_fails_on_non_existing_path() {
@@ -44,6 +56,36 @@ mod linux {
             .code_is(1)
             .stderr_contains("not a directory");
     }
+
+    #[test]
+    fn test_fails_on_unmounted_block_device() {
+        let mounts = std::fs::read_to_string("/proc/self/mounts").unwrap();
+        let Some(device) = (0..8)
+            .map(|n| format!("/dev/loop{n}"))
+            .find(|dev| Path::new(dev).exists() && !mounts.contains(&format!("{dev} ")))
+        else {
+            println!("TEST SKIPPED (no unmounted loop device)");
+            return;
+        };
+
+        new_ucmd!()
+            .arg("--unfreeze")
+            .arg(&device)
+            .fails()
+            .code_is(1)
+            .stderr_contains(format!("{device}: device is not mounted"));
+    }
+
+    #[test]
+    fn test_timeout_fails_on_non_existing_path() {
+        new_ucmd!()
+            .arg("--freeze")
+            .arg("--timeout=1")
+            .arg("/non/existing")
+            .fails()
+            .code_is(1)
+            .stderr_contains("No such file or directory");
+    }
 }
 
 #[cfg(not(target_os = "linux"))]
@@ -108,3 +108,569 @@ fn test_short_invalid_utmp_file() {
         .succeeds()
         .stdout_matches(&regex);
 }
+
+/// Serialize a single utmpx record the way login/sshd would write it to wtmp
+#[cfg(target_os = "linux")]
+fn utmp_record(ut_type: libc::c_short, user: &str, line: &str, host: &str, tv_sec: i64) -> Vec<u8> {
+    fn copy_str(dst: &mut [libc::c_char], src: &str) {
+        for (d, s) in dst.iter_mut().zip(src.bytes()) {
+            *d = s as libc::c_char;
+        }
+    }
+
+    // SAFETY: utmpx is a plain C struct for which all zeroes is a valid value
+    let mut ut: libc::utmpx = unsafe { std::mem::zeroed() };
+    ut.ut_type = ut_type;
+    copy_str(&mut ut.ut_user, user);
+    copy_str(&mut ut.ut_line, line);
+    copy_str(&mut ut.ut_host, host);
+    ut.ut_tv.tv_sec = tv_sec as _;
+
+    // SAFETY: the slice covers exactly the initialized struct
+    unsafe {
+        std::slice::from_raw_parts(
+            std::ptr::addr_of!(ut).cast::<u8>(),
+            std::mem::size_of::<libc::utmpx>(),
+        )
+    }
+    .to_vec()
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_bad_logins() {
+    let (at, mut ucmd) = at_and_ucmd!();
+    let file = "btmp";
+    let mut data = utmp_record(
+        libc::LOGIN_PROCESS,
+        "intruder",
+        "ssh:notty",
+        "",
+        1_700_000_000,
+    );
+    data.extend(utmp_record(
+        libc::USER_PROCESS,
+        "mallory",
+        "pts/3",
+        "",
+        1_700_000_060,
+    ));
+    at.write_bytes(file, &data);
+
+    let result = ucmd
+        .arg("-b")
+        .arg(format!("--file={file}"))
+        .arg("--time-format=notime")
+        .succeeds();
+    let stdout = result.stdout_str();
+    assert!(stdout.contains("intruder"));
+    assert!(stdout.contains("mallory"));
+    assert!(stdout.contains("(00:00)"));
+    assert!(stdout.contains("btmp begins"));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_fullnames() {
+    let user = "averyverylongusername";
+    let host = "build-host.long.example.com";
+    let scene = TestScenario::new(util_name!());
+    scene.fixtures.write_bytes(
+        "wtmp",
+        &utmp_record(libc::USER_PROCESS, user, "pts/1", host, 1_700_000_000),
+    );
+
+    let truncated = scene.ucmd().arg("--file=wtmp").succeeds();
+    assert!(truncated.stdout_str().contains("averyver pts/1"));
+    assert!(!truncated.stdout_str().contains(host));
+
+    scene
+        .ucmd()
+        .arg("--file=wtmp")
+        .arg("--fullnames")
+        .succeeds()
+        .stdout_contains(format!("{user} pts/1        {host} "));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_ip_numeric_hosts() {
+    let scene = TestScenario::new(util_name!());
+    let mut data = utmp_record(
+        libc::USER_PROCESS,
+        "alice",
+        "pts/1",
+        "2001:db8:0:0:0:0:0:1",
+        1_700_000_000,
+    );
+    data.extend(utmp_record(
+        libc::USER_PROCESS,
+        "bob",
+        "pts/2",
+        "10.0.0.7",
+        1_700_000_060,
+    ));
+    data.extend(utmp_record(
+        libc::USER_PROCESS,
+        "carol",
+        "pts/3",
+        "workstation",
+        1_700_000_120,
+    ));
+    scene.fixtures.write_bytes("wtmp", &data);
+
+    scene
+        .ucmd()
+        .arg("--file=wtmp")
+        .arg("--ip")
+        .succeeds()
+        .stdout_contains(" 2001:db8::1 ")
+        .stdout_contains(" 10.0.0.7 ")
+        .stdout_contains(" workstation ");
+}
+
+#[test]
+#[cfg(unix)]
+fn test_ip_conflicts_with_dns() {
+    new_ucmd!().arg("--ip").arg("--dns").fails().code_is(1);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_present() {
+    let base = 1_700_000_000;
+    let scene = TestScenario::new(util_name!());
+    let mut data = utmp_record(libc::USER_PROCESS, "alice", "pts/1", "", base + 1000);
+    data.extend(utmp_record(
+        libc::USER_PROCESS,
+        "bob",
+        "pts/2",
+        "",
+        base + 1500,
+    ));
+    data.extend(utmp_record(
+        libc::DEAD_PROCESS,
+        "",
+        "pts/1",
+        "",
+        base + 2000,
+    ));
+    data.extend(utmp_record(
+        libc::USER_PROCESS,
+        "carol",
+        "pts/3",
+        "",
+        base + 3000,
+    ));
+    data.extend(utmp_record(
+        libc::DEAD_PROCESS,
+        "",
+        "pts/3",
+        "",
+        base + 4000,
+    ));
+    scene.fixtures.write_bytes("wtmp", &data);
+
+    // alice logs out exactly at that time, carol has not logged in yet
+    scene
+        .ucmd()
+        .arg("--file=wtmp")
+        .arg(format!("--present=@{}", base + 2000))
+        .succeeds()
+        .stdout_contains("alice")
+        .stdout_contains("bob")
+        .stdout_does_not_contain("carol");
+
+    scene
+        .ucmd()
+        .arg("--file=wtmp")
+        .arg(format!("--present=@{}", base + 3500))
+        .succeeds()
+        .stdout_does_not_contain("alice")
+        .stdout_contains("bob")
+        .stdout_contains("carol");
+}
+
+#[test]
+#[cfg(unix)]
+fn test_present_invalid_time() {
+    new_ucmd!()
+        .arg("--present=definitely-invalid")
+        .fails()
+        .code_is(1)
+        .stderr_contains("invalid time value \"definitely-invalid\"");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_timestamp_format_iso_timezone() {
+    let scene = TestScenario::new(util_name!());
+    scene.fixtures.write_bytes(
+        "wtmp",
+        &utmp_record(libc::USER_PROCESS, "alice", "pts/1", "", 1_700_000_000),
+    );
+
+    for (tz, expected) in [
+        ("UTC0", "2023-11-14T22:13:20+00:00"),
+        ("EST5", "2023-11-14T17:13:20-05:00"),
+        ("IST-5:30", "2023-11-15T03:43:20+05:30"),
+    ] {
+        scene
+            .ucmd()
+            .env("TZ", tz)
+            .arg("--file=wtmp")
+            .arg("--time-format=iso")
+            .succeeds()
+            .stdout_contains(format!("pts/1                         {expected}"))
+            .stdout_contains(format!("wtmp begins {expected}"));
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_number_shorthand() {
+    let scene = TestScenario::new(util_name!());
+    let mut data = utmp_record(libc::USER_PROCESS, "alice", "pts/1", "", 1_700_000_000);
+    data.extend(utmp_record(
+        libc::USER_PROCESS,
+        "bob",
+        "pts/2",
+        "",
+        1_700_000_100,
+    ));
+    scene.fixtures.write_bytes("wtmp", &data);
+
+    let result = scene.ucmd().arg("-1").arg("--file=wtmp").succeeds();
+    assert!(result.stdout_str().contains("bob"));
+    assert!(!result.stdout_str().contains("alice"));
+
+    // A negative value passed to -n is not the shorthand
+    scene
+        .ucmd()
+        .args(&["-n", "-1", "--file=wtmp"])
+        .succeeds()
+        .stdout_contains("alice");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_multiple_files() {
+    let scene = TestScenario::new(util_name!());
+    scene.fixtures.write_bytes(
+        "wtmp1",
+        &utmp_record(libc::USER_PROCESS, "alice", "pts/1", "", 1_700_000_000),
+    );
+    let mut data = utmp_record(libc::USER_PROCESS, "bob", "pts/2", "", 1_700_000_100);
+    data.extend(utmp_record(
+        libc::USER_PROCESS,
+        "carol",
+        "pts/3",
+        "",
+        1_700_000_200,
+    ));
+    scene.fixtures.write_bytes("wtmp2", &data);
+
+    let regex = Regex::new(r"^alice .*\n\nwtmp1 begins .*\ncarol .*\nbob .*\n\nwtmp2 begins .*\n$")
+        .unwrap();
+    scene
+        .ucmd()
+        .args(&["--time-format=notime", "-f", "wtmp1", "-f", "wtmp2"])
+        .succeeds()
+        .stdout_matches(&regex);
+
+    // The limit applies across files, but every file still gets its footer
+    let regex = Regex::new(r"^carol .*\nbob .*\n\nwtmp2 begins .*\n\nwtmp1 begins .*\n$").unwrap();
+    scene
+        .ucmd()
+        .args(&["--limit=2", "-f", "wtmp2", "-f", "wtmp1"])
+        .succeeds()
+        .stdout_matches(&regex);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_multiple_files_with_missing_file() {
+    let scene = TestScenario::new(util_name!());
+    scene.fixtures.write_bytes(
+        "wtmp",
+        &utmp_record(libc::USER_PROCESS, "alice", "pts/1", "", 1_700_000_000),
+    );
+
+    // The files read before the missing one are still listed
+    scene
+        .ucmd()
+        .args(&["--time-format=notime", "-f", "wtmp", "-f", "nonexistent"])
+        .fails()
+        .code_is(1)
+        .stdout_matches(&Regex::new(r"^alice .*\n\nwtmp begins .*\n$").unwrap())
+        .stderr_contains("cannot open nonexistent: No such file or directory");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_gone_no_logout_in_truncated_file() {
+    let scene = TestScenario::new(util_name!());
+    let login = utmp_record(libc::USER_PROCESS, "alice", "pts/1", "", 1_700_000_000);
+    let reboot = utmp_record(libc::BOOT_TIME, "reboot", "~", "6.1.0", 1_700_001_000);
+
+    // The boot alice logged in to was rotated out of the file
+    let mut data = login.clone();
+    data.extend(&reboot);
+    scene.fixtures.write_bytes("wtmp", &data);
+    scene
+        .ucmd()
+        .arg("--file=wtmp")
+        .succeeds()
+        .stdout_contains("   gone - no logout")
+        .stdout_does_not_match(&Regex::new("crash").unwrap());
+
+    // With that boot in the file, the reboot means the system crashed
+    let mut data = utmp_record(libc::BOOT_TIME, "reboot", "~", "6.1.0", 1_699_999_000);
+    data.extend(&login);
+    data.extend(&reboot);
+    scene.fixtures.write_bytes("wtmp", &data);
+    scene
+        .ucmd()
+        .arg("--file=wtmp")
+        .succeeds()
+        .stdout_contains(" - crash")
+        .stdout_does_not_match(&Regex::new("gone").unwrap());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_system_entries_and_limit() {
+    let scene = TestScenario::new(util_name!());
+    let base = 1_700_000_000;
+    let mut data = utmp_record(libc::BOOT_TIME, "reboot", "~", "6.1.0", base);
+    data.extend(utmp_record(
+        libc::RUN_LVL,
+        "runlevel",
+        "~",
+        "6.1.0",
+        base + 10,
+    ));
+    data.extend(utmp_record(
+        libc::USER_PROCESS,
+        "alice",
+        "pts/1",
+        "",
+        base + 100,
+    ));
+    data.extend(utmp_record(
+        libc::RUN_LVL,
+        "shutdown",
+        "~~",
+        "6.1.0",
+        base + 1000,
+    ));
+    scene.fixtures.write_bytes("wtmp", &data);
+
+    // Reboots are shown by default, shutdowns and run level changes need -x
+    let regex = Regex::new(r"^alice .*\nreboot +system boot .*\n\nwtmp begins .*\n$").unwrap();
+    scene
+        .ucmd()
+        .args(&["--file=wtmp", "--time-format=notime"])
+        .succeeds()
+        .stdout_matches(&regex);
+    let regex = Regex::new(
+        r"^shutdown +system down .*\nalice .*\nrunlevel +\(to lvl .*\nreboot +system boot .*\n\nwtmp begins .*\n$",
+    )
+    .unwrap();
+    scene
+        .ucmd()
+        .args(&["--file=wtmp", "--time-format=notime", "-x"])
+        .succeeds()
+        .stdout_matches(&regex);
+
+    // Only printed entries count towards the limit, which does not change
+    // the start of the file reported in the footer
+    let footer = "\nwtmp begins 2023-11-14T22:13:20+00:00\n";
+    let regex = Regex::new(r"^alice .*\nreboot .*\n\n").unwrap();
+    scene
+        .ucmd()
+        .env("TZ", "UTC")
+        .args(&["--file=wtmp", "--time-format=iso", "--limit=2"])
+        .succeeds()
+        .stdout_matches(&regex)
+        .stdout_contains(footer);
+    let regex = Regex::new(r"^shutdown .*\nalice .*\n\n").unwrap();
+    scene
+        .ucmd()
+        .env("TZ", "UTC")
+        .args(&["--file=wtmp", "--time-format=iso", "--limit=2", "-x"])
+        .succeeds()
+        .stdout_matches(&regex)
+        .stdout_contains(footer);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_host_width() {
+    let scene = TestScenario::new(util_name!());
+    let host = "build-server-01.example.com";
+    scene.fixtures.write_bytes(
+        "wtmp",
+        &utmp_record(libc::USER_PROCESS, "alice", "pts/1", host, 1_700_000_000),
+    );
+
+    scene
+        .ucmd()
+        .arg("--file=wtmp")
+        .succeeds()
+        .stdout_contains(" build-server-01. ")
+        .stdout_does_not_contain(host);
+    scene
+        .ucmd()
+        .args(&["--file=wtmp", "--hostwidth=32"])
+        .succeeds()
+        .stdout_contains(format!(" {host:<32} "));
+    // Hosts that aren't IP addresses are left as they are by --dns
+    scene
+        .ucmd()
+        .args(&["--file=wtmp", "--dns"])
+        .succeeds()
+        .stdout_contains(format!(" {host:<32} "));
+    scene
+        .ucmd()
+        .args(&["--file=wtmp", "--dns", "--hostwidth=8"])
+        .succeeds()
+        .stdout_contains(" build-se ");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_compressed_file() {
+    let scene = TestScenario::new(util_name!());
+    scene.fixtures.write_bytes(
+        "wtmp.1",
+        &utmp_record(libc::USER_PROCESS, "alice", "pts/1", "", 1_700_000_000),
+    );
+    // The utmpx layout depends on the platform, so the compressed file is
+    // created on the fly rather than stored as a fixture
+    let gzip = std::process::Command::new("gzip")
+        .arg(scene.fixtures.plus("wtmp.1"))
+        .status();
+    if !gzip.is_ok_and(|status| status.success()) {
+        println!("TEST SKIPPED (gzip is not installed)");
+        return;
+    }
+
+    scene
+        .ucmd()
+        .args(&["--file=wtmp.1.gz", "--time-format=notime"])
+        .succeeds()
+        .stdout_matches(&Regex::new(r"^alice +pts/1 .*\n\nwtmp.1.gz begins .*\n$").unwrap());
+
+    scene
+        .ucmd()
+        .arg("--file=wtmp.2.zst")
+        .fails()
+        .code_is(1)
+        .stderr_contains("wtmp.2.zst: ");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_fulltimes() {
+    let scene = TestScenario::new(util_name!());
+    let base = 1_700_000_000;
+    let mut data = utmp_record(libc::USER_PROCESS, "alice", "pts/1", "", base);
+    data.extend(utmp_record(libc::DEAD_PROCESS, "", "pts/1", "", base + 600));
+    data.extend(utmp_record(
+        libc::USER_PROCESS,
+        "bob",
+        "pts/2",
+        "",
+        base + 700,
+    ));
+    data.extend(utmp_record(
+        libc::RUN_LVL,
+        "shutdown",
+        "~~",
+        "6.1.0",
+        base + 1000,
+    ));
+    scene.fixtures.write_bytes("wtmp", &data);
+
+    // A session cut short by the shutdown ends at the shutdown time
+    scene
+        .ucmd()
+        .env("TZ", "UTC")
+        .args(&["--file=wtmp", "-F"])
+        .succeeds()
+        .stdout_contains("bob      pts/2                         Tue Nov 14 22:25:00 2023 - Tue Nov 14 22:30:00 2023 (00:05)\n")
+        .stdout_contains("alice    pts/1                         Tue Nov 14 22:13:20 2023 - Tue Nov 14 22:23:20 2023 (00:10)\n");
+    scene
+        .ucmd()
+        .env("TZ", "UTC")
+        .args(&["--file=wtmp", "--time-format=full"])
+        .succeeds()
+        .stdout_contains(
+            "bob      pts/2                         Tue Nov 14 22:25:00 2023 - down   (00:05)\n",
+        );
+}
+
+#[test]
+#[cfg(unix)]
+fn test_fulltimes_conflicts_with_time_format() {
+    new_ucmd!()
+        .args(&["--fulltimes", "--time-format=iso"])
+        .fails()
+        .code_is(1);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_since_until() {
+    let scene = TestScenario::new(util_name!());
+    let base = 1_700_000_000;
+    let mut data = vec![];
+    for (i, user) in ["alice", "bob", "carol", "dave"].iter().enumerate() {
+        let tty = format!("pts/{i}");
+        data.extend(utmp_record(
+            libc::USER_PROCESS,
+            user,
+            &tty,
+            "",
+            base + 100 * i as i64,
+        ));
+    }
+    scene.fixtures.write_bytes("wtmp", &data);
+
+    // Both boundaries are included
+    let regex = Regex::new(r"^carol .*\nbob .*\n\nwtmp begins .*\n$").unwrap();
+    scene
+        .ucmd()
+        .arg("--file=wtmp")
+        .arg(format!("--since=@{}", base + 100))
+        .arg(format!("--until=@{}", base + 200))
+        .succeeds()
+        .stdout_matches(&regex);
+
+    let regex = Regex::new(r"^dave .*\ncarol .*\n\nwtmp begins .*\n$").unwrap();
+    scene
+        .ucmd()
+        .arg("--file=wtmp")
+        .arg(format!("--since=@{}", base + 101))
+        .succeeds()
+        .stdout_matches(&regex);
+
+    let regex = Regex::new(r"^bob .*\nalice .*\n\nwtmp begins .*\n$").unwrap();
+    scene
+        .ucmd()
+        .arg("--file=wtmp")
+        .arg(format!("--until=@{}", base + 199))
+        .succeeds()
+        .stdout_matches(&regex);
+}
+
+#[test]
+#[cfg(unix)]
+fn test_since_invalid_time() {
+    new_ucmd!()
+        .arg("--since=definitely-invalid")
+        .fails()
+        .code_is(1)
+        .stderr_contains("invalid time value \"definitely-invalid\"");
+}
@@ -9,3 +9,226 @@ use crate::common::util::TestScenario;
 fn test_invalid_arg() {
     new_ucmd!().arg("--definitely-invalid").fails().code_is(1);
 }
+
+/// Creates a sysfs memory tree with one online 128M block per entry of
+/// `zones`, all of them on node 0. Blocks with empty zones have no
+/// valid_zones file.
+fn write_sysroot(scene: &TestScenario, zones: &[&str]) {
+    let at = &scene.fixtures;
+    let memory = "sysroot/sys/devices/system/memory";
+    at.mkdir_all(memory);
+    at.write(&format!("{memory}/block_size_bytes"), "8000000\n");
+    for (i, zone) in zones.iter().enumerate() {
+        let block = format!("{memory}/memory{i}");
+        at.mkdir_all(&format!("{block}/node0"));
+        at.write(&format!("{block}/state"), "online\n");
+        at.write(&format!("{block}/removable"), "1\n");
+        if !zone.is_empty() {
+            at.write(&format!("{block}/valid_zones"), &format!("{zone}\n"));
+        }
+    }
+}
+
+#[test]
+fn test_output_columns() {
+    let scene = TestScenario::new(util_name!());
+    write_sysroot(&scene, &["DMA32", "Normal"]);
+
+    scene
+        .ucmd()
+        .args(&["--sysroot=sysroot", "--raw", "--output=BLOCK,size,STATE"])
+        .succeeds()
+        .stdout_is("BLOCK SIZE STATE\n0-1 256M online\n");
+}
+
+#[test]
+fn test_output_all_does_not_split_by_zones() {
+    let scene = TestScenario::new(util_name!());
+    write_sysroot(&scene, &["DMA32", "Normal", "Normal"]);
+
+    scene
+        .ucmd()
+        .args(&["--sysroot=sysroot", "--pairs", "--output-all"])
+        .succeeds()
+        .stdout_is(
+            "RANGE=\"0x0000000000000000-0x0000000017ffffff\" SIZE=\"384M\" STATE=\"online\" \
+            REMOVABLE=\"yes\" BLOCK=\"0-2\" NODE=\"0\" ZONES=\"DMA32/Normal\"\n",
+        );
+}
+
+#[test]
+fn test_split_by_zones() {
+    let scene = TestScenario::new(util_name!());
+    write_sysroot(&scene, &["DMA32", "Normal", "Normal"]);
+
+    for split in ["--split=ZONES", "--split=zones"] {
+        scene
+            .ucmd()
+            .args(&[
+                "--sysroot=sysroot",
+                "--pairs",
+                "--output=BLOCK,ZONES",
+                split,
+            ])
+            .succeeds()
+            .stdout_is("BLOCK=\"0\" ZONES=\"DMA32\"\nBLOCK=\"1-2\" ZONES=\"Normal\"\n");
+    }
+}
+
+#[test]
+fn test_split_invalid_column() {
+    new_ucmd!()
+        .arg("--split=RANGE")
+        .fails()
+        .code_is(1)
+        .stderr_contains("cannot split by RANGE");
+}
+
+#[test]
+fn test_json_has_requested_columns() {
+    let scene = TestScenario::new(util_name!());
+    write_sysroot(&scene, &["", ""]);
+
+    scene
+        .ucmd()
+        .args(&[
+            "--sysroot=sysroot",
+            "--json",
+            "--output=RANGE,NODE,ZONES,REMOVABLE",
+        ])
+        .succeeds()
+        .stdout_is(
+            r#"{
+  "memory": [
+    {
+      "range": "0x0000000000000000-0x000000000fffffff",
+      "node": "0",
+      "zones": null,
+      "removable": true
+    }
+  ]
+}
+"#,
+        );
+
+    scene
+        .ucmd()
+        .args(&["--sysroot=sysroot", "--json", "--output=SIZE"])
+        .succeeds()
+        .stdout_is(
+            r#"{
+  "memory": [
+    {
+      "size": "256M"
+    }
+  ]
+}
+"#,
+        );
+}
+
+#[test]
+fn test_all_shows_node_and_zones_per_block() {
+    let scene = TestScenario::new(util_name!());
+    write_sysroot(&scene, &["DMA32 Normal", "Normal Movable"]);
+
+    scene
+        .ucmd()
+        .args(&["--sysroot=sysroot", "--pairs", "--all"])
+        .succeeds()
+        .stdout_is(
+            "RANGE=\"0x0000000000000000-0x0000000007ffffff\" SIZE=\"128M\" STATE=\"online\" \
+            REMOVABLE=\"yes\" BLOCK=\"0\" NODE=\"0\" ZONES=\"DMA32/Normal\"\n\
+            RANGE=\"0x0000000008000000-0x000000000fffffff\" SIZE=\"128M\" STATE=\"online\" \
+            REMOVABLE=\"yes\" BLOCK=\"1\" NODE=\"0\" ZONES=\"Normal/Movable\"\n",
+        );
+
+    // Explicitly chosen columns are left alone
+    scene
+        .ucmd()
+        .args(&["--sysroot=sysroot", "--pairs", "--all", "--output=BLOCK"])
+        .succeeds()
+        .stdout_is("BLOCK=\"0\"\nBLOCK=\"1\"\n");
+}
+
+#[test]
+fn test_all_conflicts_with_split() {
+    new_ucmd!()
+        .args(&["--all", "--split=STATE"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("the argument '--all' cannot be used with '--split <list>'");
+}
+
+#[test]
+fn test_sysroot_without_block_size() {
+    let scene = TestScenario::new(util_name!());
+    write_sysroot(&scene, &["Normal"]);
+    scene
+        .fixtures
+        .remove("sysroot/sys/devices/system/memory/block_size_bytes");
+    scene
+        .ucmd()
+        .arg("--sysroot=sysroot")
+        .fails()
+        .code_is(1)
+        .stderr_only("lsmem: failed to read memory block size: No such file or directory\n");
+}
+
+#[test]
+fn test_sysroot_invalid_block_size() {
+    let scene = TestScenario::new(util_name!());
+    write_sysroot(&scene, &["Normal"]);
+    scene
+        .fixtures
+        .write("sysroot/sys/devices/system/memory/block_size_bytes", "zz\n");
+    scene
+        .ucmd()
+        .arg("--sysroot=sysroot")
+        .fails()
+        .code_is(1)
+        .stderr_only("lsmem: invalid memory block size 'zz'\n");
+}
+
+#[test]
+fn test_output_all_without_nodes() {
+    let scene = TestScenario::new(util_name!());
+    write_sysroot(&scene, &["", ""]);
+    for block in ["memory0", "memory1"] {
+        scene
+            .fixtures
+            .rmdir(&format!("sysroot/sys/devices/system/memory/{block}/node0"));
+    }
+
+    let result = scene
+        .ucmd()
+        .args(&["--sysroot=sysroot", "--output-all"])
+        .succeeds();
+    let header = result.stdout_str().lines().next().unwrap();
+    assert_eq!(
+        header.split_whitespace().collect::<Vec<_>>(),
+        [
+            "RANGE",
+            "SIZE",
+            "STATE",
+            "REMOVABLE",
+            "BLOCK",
+            "NODE",
+            "ZONES"
+        ]
+    );
+
+    scene
+        .ucmd()
+        .args(&["--sysroot=sysroot", "--output-all", "--pairs"])
+        .succeeds()
+        .stdout_is(
+            "RANGE=\"0x0000000000000000-0x000000000fffffff\" SIZE=\"256M\" STATE=\"online\" \
+             REMOVABLE=\"yes\" BLOCK=\"0-1\" NODE=\"\" ZONES=\"\"\n",
+        );
+    scene
+        .ucmd()
+        .args(&["--sysroot=sysroot", "--output-all", "--json"])
+        .succeeds()
+        .stdout_contains("      \"node\": null,\n      \"zones\": null\n");
+}